@@ -1,15 +1,12 @@
 use super::{
     error::{ClientError, ClientResult},
-    id::{PlaylistId, SnapshotId, TrackId},
+    id::{EpisodeId, ItemId, PlaylistId, SnapshotId, TrackId, UserId},
     model::{self},
     response::PaginatedResponse,
     token::Token,
 };
 use crate::{
-    api::{
-        model::TrackPartial,
-        response::{SnapshotResponse, SpotifyResponse},
-    },
+    api::response::{SnapshotResponse, SpotifyResponse},
     config::ModulateConfig,
     context::AppContext,
     db::repo::user::UserRepo,
@@ -20,7 +17,7 @@ use oauth2::{
     EndpointNotSet, EndpointSet, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken,
     Scope, StandardRevocableToken, TokenUrl, basic::*,
 };
-use reqwest::{StatusCode, Url, header};
+use reqwest::{IntoUrl, Method, RequestBuilder, StatusCode, Url, header};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
 use std::{
@@ -28,8 +25,6 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-const SPOTIFY_OAUTH2_AUTH_URL: &str = "https://accounts.spotify.com/authorize";
-const SPOTIFY_OAUTH2_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
 const SPOTIFY_OAUTH2_SCOPES: &[&str] = &[
     "user-library-read",
     "user-library-modify",
@@ -39,7 +34,8 @@ const SPOTIFY_OAUTH2_SCOPES: &[&str] = &[
     "playlist-modify-private",
 ];
 
-const SPOTIFY_API_BASE_URL: &str = "https://api.spotify.com/v1";
+/// Delay before the first retry of a transient error, doubled on each subsequent attempt
+const RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
 
 /// Client configured without token
 #[derive(Debug, Clone)]
@@ -83,9 +79,9 @@ impl Client<WithoutToken> {
         let redirect_url = RedirectUrl::new(format!("{}/callback", config.web.public_url))?;
 
         let oauth = BasicClient::new(ClientId::new(config.spotify.client_id.clone()))
-            .set_auth_uri(AuthUrl::new(SPOTIFY_OAUTH2_AUTH_URL.to_string())?)
+            .set_auth_uri(AuthUrl::new(config.spotify.oauth2_auth_url.clone())?)
             .set_client_secret(ClientSecret::new(config.spotify.client_secret.clone()))
-            .set_token_uri(TokenUrl::new(SPOTIFY_OAUTH2_TOKEN_URL.to_string())?)
+            .set_token_uri(TokenUrl::new(config.spotify.oauth2_token_url.clone())?)
             .set_redirect_uri(redirect_url);
 
         Ok(oauth)
@@ -129,14 +125,10 @@ impl Client<WithoutToken> {
         let refresh_token =
             user.token.refresh_token.ok_or_else(|| ClientError::MissingRefreshToken)?;
 
-        let http_client = oauth2::reqwest::ClientBuilder::new()
-            .redirect(oauth2::reqwest::redirect::Policy::none())
-            .build()?;
-
         let mut new_token: Token = client
             .oauth
             .exchange_refresh_token(&RefreshToken::new(refresh_token.clone()))
-            .request_async(&http_client)
+            .request_async(&ctx.http.oauth)
             .await?
             .try_into()?;
 
@@ -175,14 +167,10 @@ impl Client<WithoutToken> {
         code: String,
         pkce_verifier: PkceCodeVerifier,
     ) -> ClientResult<Token> {
-        let http_client = oauth2::reqwest::ClientBuilder::new()
-            .redirect(oauth2::reqwest::redirect::Policy::none())
-            .build()?;
-
         self.oauth
             .exchange_code(AuthorizationCode::new(code))
             .set_pkce_verifier(pkce_verifier)
-            .request_async(&http_client)
+            .request_async(&self.ctx.http.oauth)
             .await?
             .try_into()
     }
@@ -190,8 +178,17 @@ impl Client<WithoutToken> {
 
 // Methods to be used once we have a client with a valid token
 impl Client<WithToken> {
-    /// Create a request client with the appropriate authorization headers
-    fn create_request(&self) -> ClientResult<reqwest::Client> {
+    /// Build a full API URL from a path (e.g. `/me`) using the configured base URL
+    fn api_url(&self, path: &str) -> String {
+        format!(
+            "{}{}",
+            self.ctx.config.spotify.api_base_url.trim_end_matches('/'),
+            path
+        )
+    }
+
+    /// Create a request on the shared HTTP client with the appropriate authorization header
+    fn create_request(&self, method: Method, url: impl IntoUrl) -> ClientResult<RequestBuilder> {
         let access_token = self
             .token
             .0
@@ -200,78 +197,154 @@ impl Client<WithToken> {
             .access_token
             .clone();
 
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::AUTHORIZATION,
-            format!("Bearer {}", access_token).parse()?,
-        );
-
-        reqwest::Client::builder()
-            .default_headers(headers)
-            .connection_verbose(true)
-            .build()
-            .map_err(|err| err.into())
+        Ok(self.ctx.http.api.request(method, url).bearer_auth(access_token))
     }
 
     /// Fetch the current user
     pub async fn current_user(&self) -> ClientResult<model::User> {
-        tracing::debug!("GET /me");
-
-        self.map_response(
-            self.create_request()?
-                .get(format!("{}/me", SPOTIFY_API_BASE_URL))
-                .send()
-                .await?,
-        )
-        .await
+        self.send(self.create_request(Method::GET, self.api_url("/me"))?).await
     }
 
     /// Get all playlists saved by the current user, returning only basic display data
     pub async fn current_user_playlists(&self) -> ClientResult<Vec<model::PlaylistPartial>> {
-        tracing::debug!("GET /me/playlists");
+        self.collect_paginated(self.api_url("/me/playlists").as_ref(), None).await
+    }
+
+    /// Find the playlist the current user saved with a given name, out of those owned by a user
+    pub async fn current_user_playlist_by_name(
+        &self,
+        owner: &UserId,
+        name: &str,
+    ) -> ClientResult<Option<model::PlaylistPartial>> {
+        Ok(self
+            .current_user_playlists()
+            .await?
+            .into_iter()
+            .find(|playlist| playlist.owner.id == *owner && playlist.name == name))
+    }
+
+    /// Create a private playlist for a user, returning it
+    pub async fn user_playlist_create(
+        &self,
+        UserId(user_id): &UserId,
+        name: &str,
+        description: &str,
+    ) -> ClientResult<model::PlaylistPartial> {
+        #[derive(Debug, Serialize)]
+        struct CreateBody<'a> {
+            name: &'a str,
+            description: &'a str,
+            public: bool,
+        }
 
-        self.collect_paginated(
-            format!("{}/me/playlists", SPOTIFY_API_BASE_URL).as_ref(),
-            None,
+        self.send(
+            self.create_request(
+                Method::POST,
+                self.api_url(&format!("/users/{}/playlists", user_id)),
+            )?
+            .json(&CreateBody {
+                name,
+                description,
+                public: false,
+            }),
         )
         .await
     }
 
     /// Get all tracks saved by the current user, returning only the ID/URI data
-    pub async fn current_user_saved_track_partials(
-        &self,
-    ) -> ClientResult<Vec<model::TrackPartial>> {
-        tracing::debug!("GET /me/tracks");
+    pub async fn current_user_saved_item_ids(&self) -> ClientResult<Vec<ItemId>> {
+        Ok(self
+            .current_user_saved_item_entries()
+            .await?
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect())
+    }
 
+    /// Get all tracks saved by the current user, newest first, returning the ID/URI data and when each was saved
+    pub async fn current_user_saved_item_entries(&self) -> ClientResult<Vec<model::ItemEntry>> {
         #[derive(Debug, Deserialize)]
         struct Wrapper {
-            track: model::TrackPartial,
+            added_at: Option<chrono::DateTime<chrono::Utc>>,
+            track: model::ItemPartial,
         }
 
         Ok(self
-            .collect_paginated::<Wrapper>(
-                format!("{}/me/tracks", SPOTIFY_API_BASE_URL).as_ref(),
-                None,
+            .collect_paginated::<Wrapper>(self.api_url("/me/tracks").as_ref(), None)
+            .await?
+            .into_iter()
+            .filter_map(|wrapper| {
+                Some(model::ItemEntry {
+                    id: wrapper.track.into_item_id(false)?,
+                    added_at: wrapper.added_at,
+                })
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Get how many tracks the current user has saved and when the newest was saved, in one request
+    pub async fn current_user_saved_tracks_summary(
+        &self,
+    ) -> ClientResult<model::SavedTracksSummary> {
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            added_at: Option<chrono::DateTime<chrono::Utc>>,
+        }
+
+        // Saved tracks are listed newest first
+        let res: PaginatedResponse<Wrapper> = self
+            .send(
+                self.create_request(Method::GET, self.api_url("/me/tracks"))?
+                    .query(&[("limit", "1")]),
             )
+            .await?;
+
+        Ok(model::SavedTracksSummary {
+            total: res.total,
+            newest_added_at: res.items.into_iter().next().and_then(|item| item.added_at),
+        })
+    }
+
+    /// Get all tracks saved by the current user, with the data needed to apply watcher rules
+    pub async fn current_user_saved_track_details(&self) -> ClientResult<Vec<model::PlaylistItem>> {
+        Ok(self
+            .collect_paginated::<ItemDetailsWrapper>(self.api_url("/me/tracks").as_ref(), None)
             .await?
             .into_iter()
-            .map(|wrapper| wrapper.track)
+            .filter_map(ItemDetailsWrapper::into_playlist_item)
             .collect::<Vec<_>>())
     }
 
     /// Remove tracks from the current user's saved tracks by ID
     pub async fn current_user_saved_tracks_remove_ids(&self, ids: &[TrackId]) -> ClientResult<()> {
-        tracing::debug!("DELETE /me/tracks");
+        // Endpoint can only be sent a maximum of 50 IDs
+        for ids in ids.chunks(50) {
+            let res = self
+                .send::<()>(
+                    self.create_request(Method::DELETE, self.api_url("/me/tracks"))?
+                        .json(&json!({ "ids": ids })),
+                )
+                .await;
+
+            // An empty response means success
+            match res {
+                Err(_err @ ClientError::EmptyResponse) => {}
+                Err(err) => return Err(err),
+                _ => {}
+            };
+        }
+
+        Ok(())
+    }
 
+    /// Save tracks to the current user's library by ID, in the order given
+    pub async fn current_user_saved_tracks_add_ids(&self, ids: &[TrackId]) -> ClientResult<()> {
         // Endpoint can only be sent a maximum of 50 IDs
         for ids in ids.chunks(50) {
             let res = self
-                .map_response::<()>(
-                    self.create_request()?
-                        .delete(format!("{}/me/tracks", SPOTIFY_API_BASE_URL))
-                        .json(&json!({ "ids": ids }))
-                        .send()
-                        .await?,
+                .send::<()>(
+                    self.create_request(Method::PUT, self.api_url("/me/tracks"))?
+                        .json(&json!({ "ids": ids })),
                 )
                 .await;
 
@@ -286,46 +359,106 @@ impl Client<WithToken> {
         Ok(())
     }
 
+    /// Get the display data for several tracks by ID, skipping any that no longer exist
+    pub async fn tracks(&self, ids: &[TrackId]) -> ClientResult<Vec<model::Track>> {
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            tracks: Vec<Option<model::Track>>,
+        }
+
+        let mut tracks = vec![];
+
+        // Endpoint can only be sent a maximum of 50 IDs
+        for ids in ids.chunks(50) {
+            let ids = ids.iter().map(|id| id.0.as_str()).collect::<Vec<_>>().join(",");
+            let res: Wrapper = self
+                .send(
+                    self.create_request(Method::GET, self.api_url("/tracks"))?
+                        .query(&[("ids", ids)]),
+                )
+                .await?;
+
+            tracks.extend(res.tracks.into_iter().flatten());
+        }
+
+        Ok(tracks)
+    }
+
+    /// Get the display data for several episodes by ID, skipping any that no longer exist
+    pub async fn episodes(&self, ids: &[EpisodeId]) -> ClientResult<Vec<model::Episode>> {
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            episodes: Vec<Option<model::Episode>>,
+        }
+
+        let mut episodes = vec![];
+
+        // Endpoint can only be sent a maximum of 50 IDs
+        for ids in ids.chunks(50) {
+            let ids = ids.iter().map(|id| id.0.as_str()).collect::<Vec<_>>().join(",");
+            let res: Wrapper = self
+                .send(
+                    self.create_request(Method::GET, self.api_url("/episodes"))?
+                        .query(&[("ids", ids)]),
+                )
+                .await?;
+
+            episodes.extend(res.episodes.into_iter().flatten());
+        }
+
+        Ok(episodes)
+    }
+
     /// Get all a playlist by ID, returning only basic display data
     pub async fn playlist_partial(
         &self,
         PlaylistId(id): &PlaylistId,
     ) -> ClientResult<model::PlaylistPartial> {
-        tracing::debug!("GET /playlists/{}", id);
-
-        self.map_response(
-            self.create_request()?
-                .get(format!("{}/playlists/{}", SPOTIFY_API_BASE_URL, id))
+        self.send(
+            self.create_request(Method::GET, self.api_url(&format!("/playlists/{}", id)))?
                 .query(&[(
                     "fields",
                     "id,name,images,snapshot_id,external_urls(spotify),owner(id)",
-                )])
-                .send()
-                .await?,
+                )]),
         )
         .await
     }
 
+    /// Get the current snapshot ID of a playlist, which changes whenever its items do
+    pub async fn playlist_snapshot_id(
+        &self,
+        PlaylistId(id): &PlaylistId,
+    ) -> ClientResult<SnapshotId> {
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            snapshot_id: SnapshotId,
+        }
+
+        let res: Wrapper = self
+            .send(
+                self.create_request(Method::GET, self.api_url(&format!("/playlists/{}", id)))?
+                    .query(&[("fields", "snapshot_id")]),
+            )
+            .await?;
+
+        Ok(res.snapshot_id)
+    }
+
     /// Update a playlist's name
     pub async fn playlist_update_name(
         &self,
         PlaylistId(id): &PlaylistId,
         name: &str,
     ) -> ClientResult<()> {
-        tracing::debug!("PUT /playlists/{}", id);
-
         #[derive(Debug, Serialize)]
         struct UpdateBody<'a> {
             name: &'a str,
         }
 
         let res = self
-            .map_response::<()>(
-                self.create_request()?
-                    .put(format!("{}/playlists/{}", SPOTIFY_API_BASE_URL, id))
-                    .json(&UpdateBody { name })
-                    .send()
-                    .await?,
+            .send::<()>(
+                self.create_request(Method::PUT, self.api_url(&format!("/playlists/{}", id)))?
+                    .json(&UpdateBody { name }),
             )
             .await;
 
@@ -340,55 +473,89 @@ impl Client<WithToken> {
         Ok(())
     }
 
-    /// Get all tracks in a playlist, returning only the ID/URI data
-    pub async fn playlist_track_partials(
+    /// Get all items in a playlist, returning only the ID/URI data
+    pub async fn playlist_item_ids(&self, id: &PlaylistId) -> ClientResult<Vec<ItemId>> {
+        Ok(self
+            .playlist_item_entries(id)
+            .await?
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect())
+    }
+
+    /// Get all items in a playlist, returning the ID/URI data and when each was added
+    pub async fn playlist_item_entries(
         &self,
         PlaylistId(id): &PlaylistId,
-    ) -> ClientResult<Vec<TrackPartial>> {
-        tracing::debug!("GET /playlists/{}/tracks", id);
-
+    ) -> ClientResult<Vec<model::ItemEntry>> {
         #[derive(Debug, Deserialize)]
         struct Wrapper {
+            added_at: Option<chrono::DateTime<chrono::Utc>>,
+            #[serde(default)]
             is_local: bool,
-            track: Option<TrackPartial>,
+            track: Option<model::ItemPartial>,
         }
 
         Ok(self
             .collect_paginated::<Wrapper>(
-                format!("{}/playlists/{}/tracks", SPOTIFY_API_BASE_URL, id).as_ref(),
-                Some("items(is_local,track(id,type))"),
+                self.api_url(&format!("/playlists/{}/tracks", id)).as_ref(),
+                Some("items(added_at,is_local,track(id,type,uri))"),
             )
             .await?
             .into_iter()
-            .filter_map(|item| match (item.is_local, item.track) {
-                (false, Some(track)) => Some(track),
-                _ => None,
+            .filter_map(|item| {
+                Some(model::ItemEntry {
+                    id: item.track?.into_item_id(item.is_local)?,
+                    added_at: item.added_at,
+                })
             })
             .collect::<Vec<_>>())
     }
 
-    /// Add tracks to the specified playlist by ID
-    pub async fn playlist_add_ids(
+    /// Get all items in a playlist, with the data needed to apply watcher rules
+    pub async fn playlist_track_details(
         &self,
         PlaylistId(id): &PlaylistId,
-        ids: &[TrackId],
-    ) -> ClientResult<Vec<SnapshotId>> {
-        tracing::debug!("POST /playlists/{}/tracks", id);
+    ) -> ClientResult<Vec<model::PlaylistItem>> {
+        Ok(self
+            .collect_paginated::<ItemDetailsWrapper>(
+                self.api_url(&format!("/playlists/{}/tracks", id)).as_ref(),
+                Some("items(added_at,is_local,track(id,type,uri,name,explicit,duration_ms,artists(id,name),album(name,release_date)))"),
+            )
+            .await?
+            .into_iter()
+            .filter_map(ItemDetailsWrapper::into_playlist_item)
+            .collect::<Vec<_>>())
+    }
 
+    /// Insert items into the specified playlist by ID, starting at a position or at the end
+    pub async fn playlist_insert_ids(
+        &self,
+        PlaylistId(id): &PlaylistId,
+        ids: &[ItemId],
+        position: Option<u32>,
+    ) -> ClientResult<Vec<SnapshotId>> {
         let mut snapshot_ids = vec![];
 
         // Map IDs to URIs
         let uris = ids.iter().map(|id| id.uri()).collect::<Vec<_>>();
 
         // Endpoint can only be sent a maximum of 100 objects
-        for uris in uris.chunks(100) {
+        for (index, uris) in uris.chunks(100).enumerate() {
+            let mut body = json!({"uris": &uris});
+
+            // Each chunk is inserted directly after the previous one
+            if let Some(position) = position {
+                body["position"] = json!(position as usize + index * 100);
+            }
+
             let SnapshotResponse { snapshot_id } = self
-                .map_response(
-                    self.create_request()?
-                        .post(format!("{}/playlists/{}/tracks", SPOTIFY_API_BASE_URL, id))
-                        .json(&json!({"uris": &uris}))
-                        .send()
-                        .await?,
+                .send(
+                    self.create_request(
+                        Method::POST,
+                        self.api_url(&format!("/playlists/{}/tracks", id)),
+                    )?
+                    .json(&body),
                 )
                 .await?;
 
@@ -398,14 +565,12 @@ impl Client<WithToken> {
         Ok(snapshot_ids)
     }
 
-    /// Remove tracks from the specified playlist by ID
+    /// Remove items from the specified playlist by ID
     pub async fn playlist_remove_ids(
         &self,
         PlaylistId(id): &PlaylistId,
-        ids: &[TrackId],
+        ids: &[ItemId],
     ) -> ClientResult<Vec<SnapshotId>> {
-        tracing::debug!("DELETE /playlists/{}/tracks", id);
-
         let mut snapshot_ids = vec![];
 
         #[derive(Serialize)]
@@ -419,12 +584,12 @@ impl Client<WithToken> {
         // Endpoint can only be sent a maximum of 100 objects
         for tracks in tracks.chunks(100) {
             let SnapshotResponse { snapshot_id } = self
-                .map_response(
-                    self.create_request()?
-                        .delete(format!("{}/playlists/{}/tracks", SPOTIFY_API_BASE_URL, id))
-                        .json(&json!({"tracks": &tracks}))
-                        .send()
-                        .await?,
+                .send(
+                    self.create_request(
+                        Method::DELETE,
+                        self.api_url(&format!("/playlists/{}/tracks", id)),
+                    )?
+                    .json(&json!({"tracks": &tracks})),
                 )
                 .await?;
 
@@ -453,9 +618,7 @@ impl Client<WithToken> {
 
         while let Some(url) = next {
             let mut res = self
-                .map_response::<PaginatedResponse<T>>(
-                    self.create_request()?.get(url).query(&query).send().await?,
-                )
+                .send::<PaginatedResponse<T>>(self.create_request(Method::GET, url)?.query(&query))
                 .await?;
 
             next = res.next;
@@ -470,6 +633,58 @@ impl Client<WithToken> {
         Ok(items)
     }
 
+    /// Send a request, retrying throttled (429) and transient server (5xx) errors, and map the response.
+    ///
+    /// A 5xx may be returned after Spotify made the change, so POSTs, which add tracks or create
+    /// playlists every time they are sent, are only retried when throttled.
+    async fn send<T>(&self, req: reqwest::RequestBuilder) -> ClientResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let config = &self.ctx.config.spotify;
+        let max_wait = std::time::Duration::from_secs(config.retry_max_wait_secs.into());
+        let mut attempt = 1;
+
+        loop {
+            let (client, request) =
+                req.try_clone().ok_or_else(|| ClientError::RequestNotCloneable)?.build_split();
+            let request = request?;
+            let endpoint = format!("{} {}", request.method(), request.url().path());
+            let idempotent = request.method() != Method::POST;
+            tracing::debug!("{}", endpoint);
+
+            self.ctx.http.limiter.acquire().await;
+            let res = client.execute(request).await?;
+
+            if attempt >= config.retry_max_attempts {
+                return self.map_response(res).await;
+            }
+
+            let wait = match res.status() {
+                StatusCode::TOO_MANY_REQUESTS => match retry_after(&res) {
+                    // Don't wait longer than configured; give up and let the next sync try again
+                    Some(wait) if wait > max_wait => return self.map_response(res).await,
+                    Some(wait) => wait,
+                    None => backoff(attempt, max_wait),
+                },
+                status if idempotent && is_transient(status) => backoff(attempt, max_wait),
+                _ => return self.map_response(res).await,
+            };
+
+            tracing::warn!(
+                "{} returned {}, retrying in {:.1}s (attempt {}/{})",
+                endpoint,
+                res.status().as_u16(),
+                wait.as_secs_f64(),
+                attempt,
+                config.retry_max_attempts,
+            );
+
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
     /// Map a Spotify response to a generic type and handle any errors.
     async fn map_response<T>(&self, res: reqwest::Response) -> ClientResult<T>
     where
@@ -494,3 +709,280 @@ impl Client<WithToken> {
         }
     }
 }
+
+/// Status codes that indicate a temporary server-side failure worth retrying
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parse the number of seconds to wait from the Retry-After header
+fn retry_after(res: &reqwest::Response) -> Option<std::time::Duration> {
+    res.headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(std::time::Duration::from_secs)
+}
+
+/// Exponential backoff with full jitter, capped at the max wait
+fn backoff(attempt: u32, max_wait: std::time::Duration) -> std::time::Duration {
+    let exp = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt - 1)).min(max_wait);
+    exp.mul_f64(rand::random::<f64>())
+}
+
+/// A playlist or library item as returned by Spotify, before it has been identified
+#[derive(Debug, Deserialize)]
+struct ItemDetailsWrapper {
+    added_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    is_local: bool,
+    track: Option<ItemDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemDetails {
+    #[serde(flatten)]
+    partial: model::ItemPartial,
+    #[serde(flatten)]
+    details: model::TrackDetails,
+}
+
+impl ItemDetailsWrapper {
+    /// Skip items that have been removed from Spotify, which come back without a track
+    fn into_playlist_item(self) -> Option<model::PlaylistItem> {
+        let ItemDetails { partial, details } = self.track?;
+
+        Some(model::PlaylistItem {
+            id: partial.into_item_id(self.is_local)?,
+            added_at: self.added_at,
+            track: details,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        api::http::HttpClients,
+        testing::{
+            TEST_USER_ID, TestApp,
+            spotify::{Failure, FakeSpotify},
+            valid_token,
+        },
+    };
+    use reqwest::Method;
+
+    async fn client(
+        configure: impl FnOnce(&mut crate::config::SpotifyConfig),
+    ) -> (Client<WithToken>, TestApp) {
+        let mut app = TestApp::start().await;
+        configure(&mut app.ctx.config.spotify);
+        app.ctx.http = HttpClients::new(&app.ctx.config.spotify).unwrap();
+
+        let client = Client::new_with_token(app.ctx.clone(), valid_token()).unwrap();
+        (client, app)
+    }
+
+    fn me_requests(spotify: &FakeSpotify) -> usize {
+        spotify.request_count(&Method::GET, "/me")
+    }
+
+    #[tokio::test]
+    async fn it_retries_throttled_requests() {
+        let (client, app) = client(|_| {}).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::TooManyRequests {
+                retry_after: Some(0),
+            },
+            2,
+        );
+
+        assert!(client.current_user().await.is_ok());
+        assert_eq!(me_requests(&app.spotify), 3);
+    }
+
+    #[tokio::test]
+    async fn it_retries_transient_server_errors() {
+        let (client, app) = client(|_| {}).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::Status(StatusCode::BAD_GATEWAY),
+            1,
+        );
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::EmptyBody(StatusCode::SERVICE_UNAVAILABLE),
+            1,
+        );
+
+        assert!(client.current_user().await.is_ok());
+        assert_eq!(me_requests(&app.spotify), 3);
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_max_attempts() {
+        let (client, app) = client(|config| config.retry_max_attempts = 2).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::TooManyRequests { retry_after: None },
+            5,
+        );
+
+        assert!(matches!(
+            client.current_user().await,
+            Err(ClientError::TooManyRequests)
+        ));
+        assert_eq!(me_requests(&app.spotify), 2);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_wait_longer_than_max_wait() {
+        let (client, app) = client(|config| config.retry_max_wait_secs = 1).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::TooManyRequests {
+                retry_after: Some(120),
+            },
+            1,
+        );
+
+        assert!(matches!(
+            client.current_user().await,
+            Err(ClientError::TooManyRequests)
+        ));
+        assert_eq!(me_requests(&app.spotify), 1);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_client_errors() {
+        let (client, app) = client(|_| {}).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::Status(StatusCode::FORBIDDEN),
+            1,
+        );
+
+        assert!(matches!(
+            client.current_user().await,
+            Err(ClientError::ApiError { status: 403, .. })
+        ));
+        assert_eq!(me_requests(&app.spotify), 1);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_server_errors_for_additions() {
+        let (client, app) = client(|_| {}).await;
+        let playlist =
+            PlaylistId(app.spotify.add_playlist("Playlist00000000000000", TEST_USER_ID, &[]));
+        let path = format!("/playlists/{}/tracks", playlist.0);
+        let ids = [ItemId::Track(TrackId("a".into()))];
+        app.spotify.fail(
+            Method::POST,
+            &path,
+            Failure::AfterWrite(StatusCode::BAD_GATEWAY),
+            1,
+        );
+
+        // The tracks were added before the error, so sending them again would add them twice
+        assert!(matches!(
+            client.playlist_insert_ids(&playlist, &ids, None).await,
+            Err(ClientError::ApiError { status: 502, .. })
+        ));
+        assert_eq!(app.spotify.playlist_track_ids(&playlist.0), vec!["a"]);
+        assert_eq!(app.spotify.request_count(&Method::POST, &path), 1);
+    }
+
+    #[tokio::test]
+    async fn it_retries_throttled_additions() {
+        let (client, app) = client(|_| {}).await;
+        let playlist =
+            PlaylistId(app.spotify.add_playlist("Playlist00000000000000", TEST_USER_ID, &[]));
+        let path = format!("/playlists/{}/tracks", playlist.0);
+        app.spotify.fail(
+            Method::POST,
+            &path,
+            Failure::TooManyRequests {
+                retry_after: Some(0),
+            },
+            1,
+        );
+
+        client
+            .playlist_insert_ids(&playlist, &[ItemId::Track(TrackId("a".into()))], None)
+            .await
+            .unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(&playlist.0), vec!["a"]);
+        assert_eq!(app.spotify.request_count(&Method::POST, &path), 2);
+    }
+
+    #[tokio::test]
+    async fn it_retries_throttled_pages() {
+        let (client, app) = client(|_| {}).await;
+        let ids = (0..120).map(|i| format!("track{:03}", i)).collect::<Vec<_>>();
+        app.spotify
+            .set_saved_tracks(&ids.iter().map(String::as_str).collect::<Vec<_>>());
+        app.spotify.fail(
+            Method::GET,
+            "/me/tracks",
+            Failure::TooManyRequests {
+                retry_after: Some(0),
+            },
+            1,
+        );
+
+        let tracks = client.current_user_saved_item_ids().await.unwrap();
+        assert_eq!(tracks.len(), 120);
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 4);
+    }
+
+    #[tokio::test]
+    async fn it_times_out_hung_requests() {
+        let (client, app) = client(|config| config.request_timeout_secs = 1).await;
+        app.spotify.fail(
+            Method::GET,
+            "/me",
+            Failure::Delay(std::time::Duration::from_secs(60)),
+            1,
+        );
+
+        let res = client.current_user().await;
+
+        assert!(matches!(res, Err(ClientError::ReqwestError(ref err)) if err.is_timeout()));
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_expired_tokens() {
+        let app = TestApp::start().await;
+        let mut user = app.user.clone();
+        user.token.access_token = "expired".into();
+        user.token.expires_at = chrono::Utc::now() - chrono::Duration::try_minutes(1).unwrap();
+
+        let (client, user) =
+            Client::from_user_ensure_refreshed(app.ctx.clone(), user).await.unwrap();
+
+        assert_eq!(
+            user.token.access_token,
+            crate::testing::spotify::FAKE_ACCESS_TOKEN
+        );
+        assert!(!user.token.is_expired());
+        assert!(client.current_user().await.is_ok());
+        assert_eq!(app.spotify.request_count(&Method::POST, "/api/token"), 1);
+    }
+}
@@ -29,6 +29,9 @@ pub enum ClientError {
     #[error("spotify returned an empty response")]
     EmptyResponse,
 
+    #[error("request could not be retried")]
+    RequestNotCloneable,
+
     #[error(transparent)]
     ChronoOutOfRangeError(#[from] chrono::OutOfRangeError),
 
@@ -0,0 +1,44 @@
+use super::{error::ClientResult, rate_limit::RateLimiter};
+use crate::config::SpotifyConfig;
+use std::time::Duration;
+
+/// Connection-pooled HTTP clients shared by every Spotify client
+#[derive(Debug, Clone)]
+pub struct HttpClients {
+    /// Used for Spotify Web API requests
+    pub api: reqwest::Client,
+    /// Used for OAuth2 token requests, as the `oauth2` crate depends on its own version of `reqwest`
+    pub oauth: oauth2::reqwest::Client,
+    /// Shared by every Spotify client so the global request budget holds across users
+    pub limiter: RateLimiter,
+}
+
+impl HttpClients {
+    /// Build both clients using the timeouts from the Spotify config
+    pub fn new(config: &SpotifyConfig) -> ClientResult<Self> {
+        let connect_timeout = Duration::from_secs(config.connect_timeout_secs.into());
+        let read_timeout = Duration::from_secs(config.read_timeout_secs.into());
+        let request_timeout = Duration::from_secs(config.request_timeout_secs.into());
+
+        let api = reqwest::Client::builder()
+            .connect_timeout(connect_timeout)
+            .read_timeout(read_timeout)
+            .timeout(request_timeout)
+            .connection_verbose(true)
+            .build()?;
+
+        // Redirects must be disabled to prevent SSRF vulnerabilities during the token exchange
+        let oauth = oauth2::reqwest::ClientBuilder::new()
+            .redirect(oauth2::reqwest::redirect::Policy::none())
+            .connect_timeout(connect_timeout)
+            .read_timeout(read_timeout)
+            .timeout(request_timeout)
+            .build()?;
+
+        Ok(Self {
+            api,
+            oauth,
+            limiter: RateLimiter::new(config.max_requests_per_sec),
+        })
+    }
+}
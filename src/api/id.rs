@@ -8,6 +8,22 @@ pub struct PlaylistId(pub String);
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TrackId(pub String);
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EpisodeId(pub String);
+
+/// Anything that can appear in a playlist, which decides the URI it's added and removed by
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "lowercase")]
+pub enum ItemId {
+    Track(TrackId),
+    Episode(EpisodeId),
+    /// Files from the user's device have no ID and can't be added through the API, so we keep their URI
+    Local(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ArtistId(pub String);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct UserId(pub String);
 
@@ -38,6 +54,109 @@ impl TrackId {
     }
 }
 
+impl EpisodeId {
+    /// Format the episode ID as a Spotify URI
+    pub fn uri(&self) -> String {
+        format!("spotify:episode:{}", self.0)
+    }
+}
+
+impl ItemId {
+    /// Format the item as a Spotify URI
+    pub fn uri(&self) -> String {
+        match self {
+            Self::Track(id) => id.uri(),
+            Self::Episode(id) => id.uri(),
+            Self::Local(uri) => uri.clone(),
+        }
+    }
+
+    /// The kind of item, as stored alongside its ID
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Track(_) => "track",
+            Self::Episode(_) => "episode",
+            Self::Local(_) => "local",
+        }
+    }
+
+    /// The bare ID of the item, or the URI for local files
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Track(TrackId(id)) | Self::Episode(EpisodeId(id)) | Self::Local(id) => id,
+        }
+    }
+
+    /// Rebuild an item from the kind and ID returned by [`Self::kind`] and [`Self::id`]
+    pub fn from_parts(kind: &str, id: String) -> Option<Self> {
+        match kind {
+            "track" => Some(Self::Track(TrackId(id))),
+            "episode" => Some(Self::Episode(EpisodeId(id))),
+            "local" => Some(Self::Local(id)),
+            _ => None,
+        }
+    }
+
+    /// The track ID, if the item is a track
+    pub fn as_track(&self) -> Option<&TrackId> {
+        match self {
+            Self::Track(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        matches!(self, Self::Local(_))
+    }
+
+    /// The title and artist of a local file, which Spotify encodes in its URI as `spotify:local:artist:album:title:duration`
+    pub fn local_name(&self) -> Option<(String, String)> {
+        let Self::Local(uri) = self else {
+            return None;
+        };
+
+        let parts = uri.split(':').map(decode_uri_component).collect::<Vec<_>>();
+        Some((parts.get(4)?.clone(), parts.get(2)?.clone()))
+    }
+
+    /// Attempt to parse a track or episode from a valid Spotify URL or URI, treating bare IDs as tracks
+    pub fn parse_from_input(input: &str) -> ClientResult<Self> {
+        let captures = Regex::new(
+            r"^(?:https?://open\.spotify\.com/(track|episode)/|spotify:(track|episode):)?([a-zA-Z0-9]+)(?:[?#/]|$)",
+        )?
+        .captures(input)
+        .ok_or_else(|| ClientError::InvalidId(input.to_owned()))?;
+
+        let kind = captures.get(1).or(captures.get(2)).map_or("track", |kind| kind.as_str());
+        Self::from_parts(kind, captures[3].to_string())
+            .ok_or_else(|| ClientError::InvalidId(input.to_owned()))
+    }
+}
+
+/// Decode a form-encoded URI component, leaving malformed escapes as they are
+fn decode_uri_component(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if let Some(byte) = component
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()) =>
+            {
+                decoded.push(byte);
+                index += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 impl UserId {
     /// Format the user ID as a Spotify URI
     pub fn uri(&self) -> String {
@@ -70,9 +189,54 @@ mod test {
 
     #[test]
     fn it_fails_for_bad_playlist_ids() {
-        let test = |id: &str| matches!(PlaylistId::parse_from_input(id), Err(_));
+        let test = |id: &str| assert!(PlaylistId::parse_from_input(id).is_err());
 
         test("some bad id");
         test("EX3J5Phq9j7KcpkZJskhR"); // 21 characters
     }
+
+    #[test]
+    fn it_parses_valid_track_uris_and_urls() {
+        let expected = ItemId::Track(TrackId("4uLU6hMCjMI75M1A2tKUQC".to_string()));
+        let test = |id: &str| assert_eq!(ItemId::parse_from_input(id).unwrap(), expected);
+
+        test("4uLU6hMCjMI75M1A2tKUQC");
+        test("spotify:track:4uLU6hMCjMI75M1A2tKUQC");
+        test("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC?si=abc");
+    }
+
+    #[test]
+    fn it_parses_tracks_and_episodes() {
+        let test = |id: &str, expected: ItemId| {
+            assert_eq!(ItemId::parse_from_input(id).unwrap(), expected)
+        };
+
+        test("abc", ItemId::Track(TrackId("abc".into())));
+        test("spotify:track:abc", ItemId::Track(TrackId("abc".into())));
+        test(
+            "spotify:episode:abc",
+            ItemId::Episode(EpisodeId("abc".into())),
+        );
+        test(
+            "https://open.spotify.com/episode/abc?si=def",
+            ItemId::Episode(EpisodeId("abc".into())),
+        );
+        assert!(ItemId::parse_from_input("spotify:local:a:b:c:1").is_err());
+    }
+
+    #[test]
+    fn it_reads_the_name_of_local_files() {
+        let local =
+            ItemId::Local("spotify:local:The+Artist:Album:Some%20Song+%28Demo%29:180".into());
+
+        assert_eq!(
+            local.uri(),
+            "spotify:local:The+Artist:Album:Some%20Song+%28Demo%29:180"
+        );
+        assert_eq!(
+            local.local_name(),
+            Some(("Some Song (Demo)".to_string(), "The Artist".to_string()))
+        );
+        assert_eq!(ItemId::Track(TrackId("a".into())).local_name(), None);
+    }
 }
@@ -1,10 +1,15 @@
 pub mod client;
 pub mod error;
+pub mod http;
 pub mod id;
 pub mod model;
+pub mod rate_limit;
 pub mod response;
 pub mod token;
 pub mod util;
 
 /// Spotify URL for a user's "Liked Tracks" playlist.
 pub const SPOTIFY_LIKED_TRACKS_URL: &str = "https://open.spotify.com/collection/tracks";
+
+/// Spotify URL for the list of a user's playlists.
+pub const SPOTIFY_PLAYLISTS_URL: &str = "https://open.spotify.com/collection/playlists";
@@ -1,4 +1,5 @@
-use super::id::{PlaylistId, SnapshotId, TrackId, UserId};
+use super::id::{ArtistId, EpisodeId, ItemId, PlaylistId, SnapshotId, TrackId, UserId};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -10,11 +11,100 @@ pub struct User {
     pub external_urls: ExternalUrls,
 }
 
+/// The fields needed to identify a playlist item, which may be a track, an episode or a local file
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrackPartial {
-    pub id: TrackId,
+pub struct ItemPartial {
+    pub id: Option<String>,
     #[serde(rename = "type")]
     pub kind: TrackType,
+    pub uri: String,
+}
+
+impl ItemPartial {
+    /// Identify the item, falling back to its URI for local files since they have no ID
+    pub fn into_item_id(self, is_local: bool) -> Option<ItemId> {
+        match (is_local, self.kind, self.id) {
+            (true, ..) => Some(ItemId::Local(self.uri)),
+            (false, TrackType::Track, Some(id)) => Some(ItemId::Track(TrackId(id))),
+            (false, TrackType::Episode, Some(id)) => Some(ItemId::Episode(EpisodeId(id))),
+            (false, _, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub id: TrackId,
+    pub name: String,
+    pub artists: Vec<ArtistPartial>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: EpisodeId,
+    pub name: String,
+    pub show: Option<ShowPartial>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowPartial {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtistPartial {
+    #[serde(default)]
+    pub id: Option<ArtistId>,
+    pub name: String,
+}
+
+/// The ID of an item in a playlist or the user's library, along with when it was added
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemEntry {
+    pub id: ItemId,
+    pub added_at: Option<DateTime<Utc>>,
+}
+
+/// How many tracks the user has saved, and when they last saved one
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedTracksSummary {
+    pub total: u32,
+    pub newest_added_at: Option<DateTime<Utc>>,
+}
+
+/// An item in a playlist or the user's library, along with when it was added
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistItem {
+    pub id: ItemId,
+    pub added_at: Option<DateTime<Utc>>,
+    pub track: TrackDetails,
+}
+
+/// Item data used to evaluate watcher rules. Episodes and local files leave out what they don't have.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackDetails {
+    pub name: String,
+    #[serde(default)]
+    pub explicit: bool,
+    #[serde(default)]
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub artists: Vec<ArtistPartial>,
+    #[serde(default)]
+    pub album: Option<AlbumPartial>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlbumPartial {
+    pub name: String,
+    /// Formatted as `YYYY`, `YYYY-MM` or `YYYY-MM-DD` depending on the precision Spotify has
+    pub release_date: Option<String>,
+}
+
+impl AlbumPartial {
+    pub fn release_year(&self) -> Option<i32> {
+        self.release_date.as_ref()?.get(0..4)?.parse().ok()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,72 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Spaces out Spotify Web API requests so that every client together stays within a requests-per-second budget
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    /// Time between requests, or `None` if requests are unlimited
+    interval: Option<Duration>,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    /// Allow up to `max_requests_per_sec` requests per second (0 for unlimited)
+    pub fn new(max_requests_per_sec: u32) -> Self {
+        Self {
+            interval: (max_requests_per_sec > 0)
+                .then(|| Duration::from_secs(1) / max_requests_per_sec),
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Wait until the next request can be sent, reserving its slot
+    pub async fn acquire(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next_slot = self.next_slot.lock().expect("rate limiter lock poisoned");
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn it_spaces_out_requests_across_clones() {
+        let limiter = RateLimiter::new(20);
+        let other = limiter.clone();
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+            other.acquire().await;
+        }
+
+        // The first request is immediate and the other five wait 50ms each
+        assert_eq!(start.elapsed(), Duration::from_millis(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_does_not_wait_when_unlimited() {
+        let limiter = RateLimiter::new(0);
+        let start = Instant::now();
+
+        for _ in 0..100 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -26,4 +26,32 @@ pub enum Command {
 
     /// Start web and worker processes
     Start,
+
+    /// Sync every watcher that is due once and exit
+    Sync {
+        /// Only log the tracks that would be added, skipped and removed, without changing anything
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Undo a recent transfer by ID, restoring any tracks it moved
+    Undo {
+        /// ID of the transfer to undo
+        transfer_id: u32,
+    },
+
+    /// Manage database schema migrations
+    Migrate {
+        #[clap(subcommand)]
+        command: MigrateCommand,
+    },
+}
+
+#[derive(Debug, Parser)]
+pub enum MigrateCommand {
+    /// Show the current schema version and any pending migrations
+    Status,
+
+    /// Apply all pending migrations
+    Up,
 }
@@ -36,6 +36,10 @@ pub struct LogConfig {
 pub struct SyncConfig {
     pub enabled: bool,
     pub check_interval_mins: u32,
+    pub undo_max_age_days: u32,
+    pub max_consecutive_failures: u32,
+    pub max_concurrency: u32,
+    pub shutdown_timeout_secs: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -55,6 +59,15 @@ pub struct WebConfig {
 pub struct SpotifyConfig {
     pub client_id: String,
     pub client_secret: String,
+    pub api_base_url: String,
+    pub oauth2_auth_url: String,
+    pub oauth2_token_url: String,
+    pub retry_max_attempts: u32,
+    pub retry_max_wait_secs: u32,
+    pub max_requests_per_sec: u32,
+    pub connect_timeout_secs: u32,
+    pub read_timeout_secs: u32,
+    pub request_timeout_secs: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,6 +36,16 @@ pub fn parse(override_path: Option<String>) -> ConfigResult<ModulateConfig> {
     Ok(config.extract()?)
 }
 
+/// Parse only the embedded default config, ignoring local files and the environment
+#[cfg(test)]
+pub fn parse_default() -> ConfigResult<ModulateConfig> {
+    Ok(Figment::new()
+        .merge(Toml::string(std::str::from_utf8(
+            get_default_data().as_ref(),
+        )?))
+        .extract()?)
+}
+
 pub fn get_config_dir() -> ConfigResult<PathBuf> {
     let override_path = std::env::var(ENV_CONFIG_HOME_PATH)
         .ok()
@@ -5,4 +5,5 @@ use r2d2_sqlite::SqliteConnectionManager;
 pub struct AppContext {
     pub config: crate::config::ModulateConfig,
     pub db: Pool<SqliteConnectionManager>,
+    pub http: crate::api::http::HttpClients,
 }
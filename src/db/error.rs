@@ -6,8 +6,28 @@ pub enum DbError {
     #[error("invalid ID: {0}")]
     InvalidId(String),
 
-    #[error("invalid sync interval: {0}")]
-    InvalidSyncInterval(String),
+    #[error("invalid sync schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error("invalid time zone: {0}")]
+    InvalidTimeZone(String),
+
+    #[error("invalid track action: {0}")]
+    InvalidTrackAction(String),
+
+    #[error("invalid item type: {0}")]
+    InvalidItemType(String),
+
+    #[error("invalid journal batch status: {0}")]
+    InvalidBatchStatus(String),
+
+    #[error("invalid playlist template: {0}")]
+    InvalidPlaylistTemplate(String),
+
+    #[error(
+        "database schema version {current} is newer than the latest known version {latest}; please upgrade modulate"
+    )]
+    SchemaTooNew { current: u32, latest: u32 },
 
     #[error(transparent)]
     IOError(#[from] std::io::Error),
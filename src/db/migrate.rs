@@ -0,0 +1,215 @@
+use super::error::{DbError, DbResult};
+use r2d2_sqlite::rusqlite::Connection;
+
+/// A schema change, applied in order of its version
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+/// All migrations embedded in the binary. New migrations must be appended with the next version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_initial_tables",
+        sql: include_str!("migrations/0001_create_initial_tables.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "nullable_transfer_errors",
+        sql: include_str!("migrations/0002_nullable_transfer_errors.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "create_transfer_tracks",
+        sql: include_str!("migrations/0003_create_transfer_tracks.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "add_transfer_undo_of",
+        sql: include_str!("migrations/0004_add_transfer_undo_of.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "add_watcher_rules",
+        sql: include_str!("migrations/0005_add_watcher_rules.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "add_transfer_item_types",
+        sql: include_str!("migrations/0006_add_transfer_item_types.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "add_watcher_pause_state",
+        sql: include_str!("migrations/0007_add_watcher_pause_state.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "add_watcher_schedules",
+        sql: include_str!("migrations/0008_add_watcher_schedules.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "create_transfer_journals",
+        sql: include_str!("migrations/0009_create_transfer_journals.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "add_watcher_options",
+        sql: include_str!("migrations/0010_add_watcher_options.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "add_watcher_snapshots",
+        sql: include_str!("migrations/0011_add_watcher_snapshots.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "add_watcher_extra_playlists",
+        sql: include_str!("migrations/0012_add_watcher_extra_playlists.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "create_mirror_states",
+        sql: include_str!("migrations/0013_create_mirror_states.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "add_watcher_period_playlists",
+        sql: include_str!("migrations/0014_add_watcher_period_playlists.sql"),
+    },
+];
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub current: u32,
+    pub latest: u32,
+    pub pending: Vec<&'static Migration>,
+}
+
+/// The version of the newest migration this binary knows about
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|migration| migration.version).unwrap_or_default()
+}
+
+/// Read the schema version stored in the database header
+pub fn current_version(conn: &Connection) -> DbResult<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Compare the database schema version with the migrations in this binary
+pub fn status(conn: &Connection) -> DbResult<MigrationStatus> {
+    let current = current_version(conn)?;
+
+    Ok(MigrationStatus {
+        current,
+        latest: latest_version(),
+        pending: MIGRATIONS.iter().filter(|migration| migration.version > current).collect(),
+    })
+}
+
+/// Apply all pending migrations, each in its own transaction, returning the ones that were applied
+pub fn up(conn: &mut Connection) -> DbResult<Vec<&'static Migration>> {
+    let status = status(conn)?;
+
+    // Refuse to touch a database that was migrated by a newer version of the app
+    if status.current > status.latest {
+        return Err(DbError::SchemaTooNew {
+            current: status.current,
+            latest: status.latest,
+        });
+    }
+
+    for migration in &status.pending {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+
+        tracing::info!(
+            "Applied migration {:04}_{}",
+            migration.version,
+            migration.name
+        );
+    }
+
+    Ok(status.pending)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn legacy_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute_batch(
+            "INSERT INTO transfers (watcher_id, num_tracks_transferred, error, synced_at, created_at)
+                VALUES (1, 3, '', 'now', 'now'), (1, 0, 'something broke', 'now', 'now')",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn it_migrates_a_new_database_to_the_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let applied = up(&mut conn).unwrap();
+
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(up(&mut conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_adopts_databases_created_before_migrations() {
+        let mut conn = legacy_db();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        up(&mut conn).unwrap();
+
+        let errors = conn
+            .prepare("SELECT error FROM transfers ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get::<_, Option<String>>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(errors, vec![None, Some("something broke".to_string())]);
+    }
+
+    #[test]
+    fn it_converts_sync_intervals_to_schedules() {
+        let mut conn = legacy_db();
+        conn.execute_batch(
+            "INSERT INTO watchers (user_uri, playlist_from, playlist_to, should_remove, sync_interval, created_at)
+                VALUES ('user', '_liked', 'a', 0, 'hour', 'now'), ('user', '_liked', 'b', 0, 'week', 'now')",
+        )
+        .unwrap();
+
+        up(&mut conn).unwrap();
+
+        let schedules = conn
+            .prepare("SELECT schedule FROM watchers ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(schedules, vec!["every 1h", "every 1w"]);
+    }
+
+    #[test]
+    fn it_refuses_to_migrate_a_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+
+        assert!(matches!(up(&mut conn), Err(DbError::SchemaTooNew { .. })));
+    }
+}
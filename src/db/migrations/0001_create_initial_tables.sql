@@ -0,0 +1,30 @@
+-- Tables as they were created before versioned migrations existed, so existing installs can be adopted as-is
+CREATE TABLE IF NOT EXISTS users (
+    id          INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_uri    TEXT    NOT NULL UNIQUE,
+    token       TEXT    NOT NULL,
+    created_at  TEXT    NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS watchers (
+    id              INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_uri        TEXT    NOT NULL,
+    playlist_from   TEXT    NOT NULL,
+    playlist_to     TEXT    NOT NULL,
+    should_remove   BOOLEAN NOT NULL CHECK (should_remove IN (0, 1)),
+    sync_interval   TEXT    NOT NULL,
+    last_sync_at    TEXT,
+    next_sync_at    TEXT,
+    created_at      TEXT    NOT NULL,
+
+    UNIQUE (user_uri, playlist_from, playlist_to)
+);
+
+CREATE TABLE IF NOT EXISTS transfers (
+    id                      INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    watcher_id              INTEGER NOT NULL,
+    num_tracks_transferred  INTEGER NOT NULL,
+    error                   TEXT    NOT NULL,
+    synced_at               TEXT    NOT NULL,
+    created_at              TEXT    NOT NULL
+);
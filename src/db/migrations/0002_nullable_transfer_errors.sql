@@ -0,0 +1,17 @@
+-- Successful transfers were stored with an empty error string; store NULL instead and index by watcher
+CREATE TABLE transfers_new (
+    id                      INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    watcher_id              INTEGER NOT NULL,
+    num_tracks_transferred  INTEGER NOT NULL,
+    error                   TEXT,
+    synced_at               TEXT    NOT NULL,
+    created_at              TEXT    NOT NULL
+);
+
+INSERT INTO transfers_new (id, watcher_id, num_tracks_transferred, error, synced_at, created_at)
+    SELECT id, watcher_id, num_tracks_transferred, NULLIF(error, ''), synced_at, created_at FROM transfers;
+
+DROP TABLE transfers;
+ALTER TABLE transfers_new RENAME TO transfers;
+
+CREATE INDEX transfers_watcher_id ON transfers (watcher_id);
@@ -0,0 +1,14 @@
+-- Individual track changes made by each transfer, so a track's history can be traced
+CREATE TABLE transfer_tracks (
+    id              INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    transfer_id     INTEGER NOT NULL,
+    track_id        TEXT    NOT NULL,
+    action          TEXT    NOT NULL,
+    playlist_from   TEXT    NOT NULL,
+    playlist_to     TEXT    NOT NULL,
+    position        INTEGER,
+    created_at      TEXT    NOT NULL
+);
+
+CREATE INDEX transfer_tracks_transfer_id ON transfer_tracks (transfer_id);
+CREATE INDEX transfer_tracks_track_id ON transfer_tracks (track_id);
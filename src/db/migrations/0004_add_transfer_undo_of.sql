@@ -0,0 +1,4 @@
+-- Undoing a transfer is recorded as a new transfer that points back at the one it reverted
+ALTER TABLE transfers ADD COLUMN undo_of INTEGER;
+
+CREATE INDEX transfers_undo_of ON transfers (undo_of);
@@ -0,0 +1,2 @@
+-- Optional filter rules for each watcher, stored as JSON (NULL transfers every track)
+ALTER TABLE watchers ADD COLUMN rules TEXT;
@@ -0,0 +1,5 @@
+-- Playlists can hold podcast episodes and local files as well as tracks, so each change records the kind of item
+ALTER TABLE transfer_tracks ADD COLUMN item_type TEXT NOT NULL DEFAULT 'track';
+
+-- Local files can't be transferred through the API, so each transfer counts the ones it had to skip
+ALTER TABLE transfers ADD COLUMN num_items_skipped INTEGER NOT NULL DEFAULT 0;
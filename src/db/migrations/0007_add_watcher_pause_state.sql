@@ -0,0 +1,4 @@
+-- Watchers can be paused by their owner, or by the worker after too many failed syncs in a row
+ALTER TABLE watchers ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE watchers ADD COLUMN paused_reason TEXT;
+ALTER TABLE watchers ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
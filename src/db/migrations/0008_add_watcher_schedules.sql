@@ -0,0 +1,8 @@
+-- Fixed intervals become schedules, which can also be any duration or a cron expression
+UPDATE watchers SET sync_interval = 'every 1h' WHERE sync_interval = 'hour';
+UPDATE watchers SET sync_interval = 'every 1d' WHERE sync_interval = 'day';
+UPDATE watchers SET sync_interval = 'every 1w' WHERE sync_interval = 'week';
+ALTER TABLE watchers RENAME COLUMN sync_interval TO schedule;
+
+-- Cron schedules run in the time zone of the user who owns the watcher
+ALTER TABLE users ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'UTC';
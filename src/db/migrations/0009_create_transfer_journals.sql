@@ -0,0 +1,20 @@
+-- Write-ahead journal of the changes a transfer is about to make, so a transfer interrupted by a crash can be finished
+CREATE TABLE transfer_journals (
+    id              INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    watcher_id      INTEGER NOT NULL,
+    plan            TEXT    NOT NULL,
+    synced_at       TEXT    NOT NULL,
+    created_at      TEXT    NOT NULL
+);
+
+-- Each API call a journaled transfer makes, in the order they are made
+CREATE TABLE transfer_journal_batches (
+    id              INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    journal_id      INTEGER NOT NULL,
+    action          TEXT    NOT NULL,
+    playlist        TEXT    NOT NULL,
+    items           TEXT    NOT NULL,
+    status          TEXT    NOT NULL DEFAULT 'pending'
+);
+
+CREATE INDEX transfer_journal_batches_journal_id ON transfer_journal_batches (journal_id);
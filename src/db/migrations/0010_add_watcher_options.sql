@@ -0,0 +1,5 @@
+-- Optional transfer settings for each watcher, stored as JSON (NULL uses the defaults)
+ALTER TABLE watchers ADD COLUMN options TEXT;
+
+-- Where a batch of additions is inserted in the target, or NULL to append them
+ALTER TABLE transfer_journal_batches ADD COLUMN position INTEGER;
@@ -0,0 +1,3 @@
+-- Fingerprints of the source and target as of the watcher's last sync, so unchanged playlists aren't read again
+ALTER TABLE watchers ADD COLUMN source_snapshot TEXT;
+ALTER TABLE watchers ADD COLUMN target_snapshot TEXT;
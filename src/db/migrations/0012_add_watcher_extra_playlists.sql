@@ -0,0 +1,4 @@
+-- Further sources merged into and targets copied to by a watcher, stored as JSON arrays of playlist values
+-- (NULL when the watcher only has its own source and target)
+ALTER TABLE watchers ADD COLUMN extra_sources TEXT;
+ALTER TABLE watchers ADD COLUMN extra_targets TEXT;
@@ -0,0 +1,6 @@
+-- The tracks both playlists of a mirroring watcher held after its last sync, so additions can be told apart from removals
+CREATE TABLE mirror_states (
+    watcher_id      INTEGER NOT NULL PRIMARY KEY,
+    items           TEXT    NOT NULL,
+    updated_at      TEXT    NOT NULL
+);
@@ -0,0 +1,3 @@
+-- The playlist each templated target of a watcher resolved to for the current period, stored as a JSON array
+-- so the user's playlists are only searched again once the period ends
+ALTER TABLE watchers ADD COLUMN period_playlists TEXT;
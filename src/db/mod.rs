@@ -4,58 +4,27 @@ use r2d2_sqlite::SqliteConnectionManager;
 use std::{fs::File, path};
 
 pub mod error;
+pub mod migrate;
 pub mod model;
 pub mod repo;
 
+/// Open the database and bring its schema up to date
 pub fn init(db_path: &path::PathBuf) -> DbResult<Pool<SqliteConnectionManager>> {
+    let db = connect(db_path)?;
+
+    migrate::up(&mut *db.get()?)?;
+
+    Ok(db)
+}
+
+/// Open the database without running any migrations
+pub fn connect(db_path: &path::PathBuf) -> DbResult<Pool<SqliteConnectionManager>> {
     // Create database file if it doesn't already exist
     if !db_path.try_exists()? {
         File::create(db_path)?;
     }
 
     let db_manager = SqliteConnectionManager::file(db_path);
-    let db = Pool::new(db_manager)?;
-
-    let conn = db.get()?;
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS users (
-            id          INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
-            user_uri    TEXT    NOT NULL UNIQUE,
-            token       TEXT    NOT NULL,
-            created_at  TEXT    NOT NULL
-        )",
-        [],
-    )?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS watchers (
-            id              INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
-            user_uri        TEXT    NOT NULL,
-            playlist_from   TEXT    NOT NULL,
-            playlist_to     TEXT    NOT NULL,
-            should_remove   BOOLEAN NOT NULL CHECK (should_remove IN (0, 1)),
-            sync_interval   TEXT    NOT NULL,
-            last_sync_at    TEXT,
-            next_sync_at    TEXT,
-            created_at      TEXT    NOT NULL,
-
-            UNIQUE (user_uri, playlist_from, playlist_to)
-        )",
-        [],
-    )?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS transfers (
-            id                      INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
-            watcher_id              INTEGER NOT NULL,
-            num_tracks_transferred  INTEGER NOT NULL,
-            error                   TEXT    NOT NULL,
-            synced_at               TEXT    NOT NULL,
-            created_at              TEXT    NOT NULL
-        )",
-        [],
-    )?;
-
-    Ok(db)
+    Ok(Pool::new(db_manager)?)
 }
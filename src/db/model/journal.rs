@@ -0,0 +1,103 @@
+use super::{
+    playlist::PlaylistType,
+    transfer_track::{TrackAction, TrackChange},
+};
+use crate::{api::id::ItemId, db::error::DbError};
+use chrono::{DateTime, Utc};
+use r2d2_sqlite::rusqlite::Row;
+use std::{fmt::Display, str::FromStr};
+
+pub const COLUMNS: &str = "id, watcher_id, plan, synced_at";
+
+pub const BATCH_COLUMNS: &str = "id, action, playlist, items, status, position";
+
+/// A transfer that has been planned but not yet logged. Only exists while the transfer is running, or
+/// if it was interrupted before it could finish.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    pub id: u32,
+    pub watcher_id: u32,
+    /// Every change the transfer planned to make, which is logged once it finishes
+    pub plan: Vec<TrackChange>,
+    pub synced_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row<'_>> for Journal {
+    type Error = DbError;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.get(0)?,
+            watcher_id: row.get(1)?,
+            plan: serde_json::from_str(&row.get::<_, String>(2)?)?,
+            synced_at: row.get::<_, String>(3)?.parse()?,
+        })
+    }
+}
+
+/// A single API call to add items to or remove items from a playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedBatch {
+    /// Either `Added` or `Removed`
+    pub action: TrackAction,
+    pub playlist: PlaylistType,
+    pub items: Vec<ItemId>,
+    /// Where additions are inserted in the playlist, or `None` to append them
+    pub position: Option<u32>,
+}
+
+/// A batch of a journaled transfer, along with how far it got
+#[derive(Debug, Clone)]
+pub struct JournalBatch {
+    pub id: u32,
+    pub batch: PlannedBatch,
+    pub status: BatchStatus,
+}
+
+impl TryFrom<&Row<'_>> for JournalBatch {
+    type Error = DbError;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.get(0)?,
+            batch: PlannedBatch {
+                action: row.get::<_, String>(1)?.parse()?,
+                playlist: PlaylistType::try_from_value(&row.get::<_, String>(2)?)?,
+                items: serde_json::from_str(&row.get::<_, String>(3)?)?,
+                position: row.get(5)?,
+            },
+            status: row.get::<_, String>(4)?.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchStatus {
+    Pending,
+    /// The request was sent, but it isn't known whether Spotify applied it
+    Started,
+    Done,
+}
+
+impl Display for BatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::Pending => write!(f, "pending"),
+            Self::Started => write!(f, "started"),
+            Self::Done => write!(f, "done"),
+        }
+    }
+}
+
+impl FromStr for BatchStatus {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "pending" => Self::Pending,
+            "started" => Self::Started,
+            "done" => Self::Done,
+            _ => return Err(DbError::InvalidBatchStatus(s.to_owned())),
+        })
+    }
+}
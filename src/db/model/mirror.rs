@@ -0,0 +1,32 @@
+use crate::{api::id::ItemId, db::error::DbError};
+use chrono::{DateTime, Utc};
+use r2d2_sqlite::rusqlite::Row;
+use serde::{Deserialize, Serialize};
+
+pub const COLUMNS: &str = "items";
+
+/// What a mirroring watcher's playlists looked like once its last sync finished. A track missing from
+/// one playlist that both held then was removed from it, while one neither held was added.
+#[derive(Debug, Clone)]
+pub struct MirrorState {
+    pub items: Vec<MirrorItem>,
+}
+
+/// A track both of a mirror's playlists held, and when it was added to each, if that is known
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorItem {
+    pub id: ItemId,
+    /// `None` when the mirror added the track itself, as the time is only known once it is read back
+    pub source_added_at: Option<DateTime<Utc>>,
+    pub target_added_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<&Row<'_>> for MirrorState {
+    type Error = DbError;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            items: serde_json::from_str(&row.get::<_, String>(0)?)?,
+        })
+    }
+}
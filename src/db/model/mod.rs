@@ -1,4 +1,11 @@
+pub mod journal;
+pub mod mirror;
+pub mod options;
 pub mod playlist;
+pub mod rules;
+pub mod schedule;
+pub mod template;
 pub mod transfer;
+pub mod transfer_track;
 pub mod user;
 pub mod watcher;
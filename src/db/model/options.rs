@@ -0,0 +1,230 @@
+use crate::api::{id::PlaylistId, model::TrackDetails};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use validator::Validate;
+
+/// Optional settings that change how a watcher transfers the tracks it has picked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct WatcherOptions {
+    /// Where new tracks go in the target playlist, and in what order
+    pub insert_order: InsertOrder,
+    /// Transfer every occurrence of a track that is in the source more than once, rather than just the first
+    pub keep_duplicates: bool,
+    /// Keep the source and target identical, copying additions and removals in both directions
+    pub mirror: bool,
+    /// How a mirror settles a track it can't tell was added to one playlist or removed from the other
+    pub conflict_policy: ConflictPolicy,
+    /// Most tracks each target may hold, evicting older tracks to make room for new ones
+    #[validate(range(min = 1, max = 10000))]
+    pub max_tracks: Option<u32>,
+    /// Which tracks are evicted from a target that would hold more than `max_tracks`
+    pub eviction_policy: EvictionPolicy,
+    /// Playlist that evicted tracks are moved to, rather than just removed from the target
+    pub overflow_playlist: Option<PlaylistId>,
+}
+
+impl WatcherOptions {
+    /// Whether every option is left at its default
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The order new tracks are inserted into the target playlist in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertOrder {
+    /// At the end of the target, oldest first, so the target reads in the order tracks were added to the source
+    #[default]
+    Append,
+    /// At the start of the target, so the newest track ends up first
+    Prepend,
+    /// At the end of the target, sorted by the track's name
+    SortByName,
+    /// At the end of the target, sorted by the track's first artist
+    SortByArtist,
+    /// At the end of the target, sorted by the track's album
+    SortByAlbum,
+    /// At the end of the target, oldest release first
+    SortByReleaseDate,
+}
+
+impl InsertOrder {
+    /// Whether the order needs track details that are not returned alongside IDs
+    pub fn needs_details(&self) -> bool {
+        !matches!(self, Self::Append | Self::Prepend)
+    }
+
+    /// The key tracks are sorted by, if this order sorts them. Items without the field sort first.
+    pub fn sort_key(&self, track: &TrackDetails) -> Option<String> {
+        let key = match self {
+            Self::Append | Self::Prepend => return None,
+            Self::SortByName => Some(track.name.clone()),
+            Self::SortByArtist => track.artists.first().map(|artist| artist.name.clone()),
+            Self::SortByAlbum => track.album.as_ref().map(|album| album.name.clone()),
+            Self::SortByReleaseDate => {
+                track.album.as_ref().and_then(|album| album.release_date.clone())
+            }
+        };
+
+        Some(key.unwrap_or_default().to_lowercase())
+    }
+
+    /// A short description for the dashboard
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Append => "added to the end, oldest first",
+            Self::Prepend => "added to the start, newest first",
+            Self::SortByName => "added to the end, sorted by name",
+            Self::SortByArtist => "added to the end, sorted by artist",
+            Self::SortByAlbum => "added to the end, sorted by album",
+            Self::SortByReleaseDate => "added to the end, sorted by release date",
+        }
+    }
+}
+
+/// What a mirror does with a track that is only in one of its playlists, when it can't tell whether the
+/// track was added to that playlist or removed from the other. This happens on the first sync, and when
+/// a track was removed from one playlist and added again to the other since the last sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Copy the track to the other playlist, so nothing is ever lost
+    #[default]
+    Keep,
+    /// Remove the track from the playlist that has it
+    Remove,
+    /// Do whatever makes the target match the source
+    PreferSource,
+    /// Do whatever makes the source match the target
+    PreferTarget,
+}
+
+impl ConflictPolicy {
+    /// Whether a conflicting track is kept, given which playlist it is in
+    pub fn keeps(&self, in_source: bool) -> bool {
+        match self {
+            Self::Keep => true,
+            Self::Remove => false,
+            Self::PreferSource => in_source,
+            Self::PreferTarget => !in_source,
+        }
+    }
+}
+
+/// Which tracks make room for new ones in a target that is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// The tracks that were added to the target longest ago
+    #[default]
+    Oldest,
+    /// Any of the target's tracks, picked at random
+    Random,
+}
+
+impl EvictionPolicy {
+    /// A short description for the dashboard
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Oldest => "the oldest tracks",
+            Self::Random => "random tracks",
+        }
+    }
+}
+
+impl Display for EvictionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Oldest => write!(f, "oldest"),
+            Self::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keep => write!(f, "keep"),
+            Self::Remove => write!(f, "remove"),
+            Self::PreferSource => write!(f, "prefer_source"),
+            Self::PreferTarget => write!(f, "prefer_target"),
+        }
+    }
+}
+
+impl Display for InsertOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Append => write!(f, "append"),
+            Self::Prepend => write!(f, "prepend"),
+            Self::SortByName => write!(f, "sort_by_name"),
+            Self::SortByArtist => write!(f, "sort_by_artist"),
+            Self::SortByAlbum => write!(f, "sort_by_album"),
+            Self::SortByReleaseDate => write!(f, "sort_by_release_date"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::model::{AlbumPartial, ArtistPartial};
+
+    #[test]
+    fn it_displays_insert_orders_as_they_are_serialized() {
+        for order in [
+            InsertOrder::Append,
+            InsertOrder::Prepend,
+            InsertOrder::SortByName,
+            InsertOrder::SortByArtist,
+            InsertOrder::SortByAlbum,
+            InsertOrder::SortByReleaseDate,
+        ] {
+            assert_eq!(serde_json::to_value(order).unwrap(), order.to_string());
+        }
+    }
+
+    #[test]
+    fn it_displays_conflict_policies_as_they_are_serialized() {
+        for policy in [
+            ConflictPolicy::Keep,
+            ConflictPolicy::Remove,
+            ConflictPolicy::PreferSource,
+            ConflictPolicy::PreferTarget,
+        ] {
+            assert_eq!(serde_json::to_value(policy).unwrap(), policy.to_string());
+        }
+    }
+
+    #[test]
+    fn it_displays_eviction_policies_as_they_are_serialized() {
+        for policy in [EvictionPolicy::Oldest, EvictionPolicy::Random] {
+            assert_eq!(serde_json::to_value(policy).unwrap(), policy.to_string());
+        }
+    }
+
+    #[test]
+    fn it_sorts_tracks_case_insensitively_with_missing_fields_first() {
+        let track = TrackDetails {
+            name: "Song".to_owned(),
+            explicit: false,
+            duration_ms: 0,
+            artists: vec![ArtistPartial {
+                id: None,
+                name: "Band".to_owned(),
+            }],
+            album: Some(AlbumPartial {
+                name: "Record".to_owned(),
+                release_date: None,
+            }),
+        };
+
+        assert_eq!(InsertOrder::Append.sort_key(&track), None);
+        assert_eq!(InsertOrder::SortByName.sort_key(&track).unwrap(), "song");
+        assert_eq!(InsertOrder::SortByArtist.sort_key(&track).unwrap(), "band");
+        assert_eq!(InsertOrder::SortByAlbum.sort_key(&track).unwrap(), "record");
+        assert_eq!(InsertOrder::SortByReleaseDate.sort_key(&track).unwrap(), "");
+    }
+}
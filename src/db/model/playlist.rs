@@ -1,3 +1,4 @@
+use super::template::PlaylistTemplate;
 use crate::{
     api::id::PlaylistId,
     db::error::{DbError, DbResult},
@@ -8,10 +9,15 @@ use std::fmt::Display;
 /// Value that represents the built-in "Liked Tracks" playlist, as it has to be handled differently than regular playlists.
 pub const LIKED_PLAYLIST_VALUE: &str = "_liked";
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// Prefix of values that name a playlist per period rather than a fixed playlist
+pub const TEMPLATE_PLAYLIST_PREFIX: &str = "template:";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum PlaylistType {
     Saved,
     Id(PlaylistId),
+    /// A playlist of the user's that is found or created by name for each period. Can only be transferred to.
+    Template(PlaylistTemplate),
 }
 
 impl Display for PlaylistType {
@@ -19,6 +25,7 @@ impl Display for PlaylistType {
         match self {
             PlaylistType::Saved => write!(f, "Liked Tracks"),
             PlaylistType::Id(PlaylistId(value)) => write!(f, "{}", value),
+            PlaylistType::Template(template) => write!(f, "{}", template),
         }
     }
 }
@@ -30,11 +37,16 @@ impl PlaylistType {
         match self {
             PlaylistType::Saved => LIKED_PLAYLIST_VALUE.to_string(),
             PlaylistType::Id(id) => id.uri(),
+            PlaylistType::Template(template) => format!("{}{}", TEMPLATE_PLAYLIST_PREFIX, template),
         }
     }
 
     /// Convert from value string.
     pub fn try_from_value(value: &str) -> DbResult<Self> {
+        if let Some(template) = value.strip_prefix(TEMPLATE_PLAYLIST_PREFIX) {
+            return Ok(Self::Template(template.parse()?));
+        }
+
         Ok(match value {
             LIKED_PLAYLIST_VALUE => Self::Saved,
             _ => Self::Id(
@@ -43,4 +55,22 @@ impl PlaylistType {
             ),
         })
     }
+
+    /// Convert a list of playlists to a JSON array for storage, storing nothing if it is empty.
+    pub fn list_to_value(playlists: &[Self]) -> DbResult<Option<String>> {
+        let values = playlists.iter().map(Self::to_value).collect::<Vec<_>>();
+        Ok((!values.is_empty()).then(|| serde_json::to_string(&values)).transpose()?)
+    }
+
+    /// Convert from a stored JSON array of value strings.
+    pub fn try_list_from_value(value: Option<String>) -> DbResult<Vec<Self>> {
+        let Some(value) = value else {
+            return Ok(vec![]);
+        };
+
+        serde_json::from_str::<Vec<String>>(&value)?
+            .iter()
+            .map(|value| Self::try_from_value(value))
+            .collect()
+    }
 }
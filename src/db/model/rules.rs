@@ -0,0 +1,228 @@
+use crate::api::model::PlaylistItem;
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// Optional filters that decide which tracks in the source playlist a watcher transfers.
+/// Every rule that is set must match for a track to be transferred.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+#[validate(schema(function = "validate_ranges"))]
+pub struct WatcherRules {
+    /// Only tracks added to the source playlist within this many days
+    #[validate(range(min = 1, max = 3650))]
+    pub added_within_days: Option<u32>,
+    /// Skip tracks marked as explicit
+    pub exclude_explicit: bool,
+    #[validate(range(max = 86400))]
+    pub min_duration_secs: Option<u32>,
+    #[validate(range(max = 86400))]
+    pub max_duration_secs: Option<u32>,
+    /// Artist names or IDs, at least one of which must be on the track
+    #[validate(length(max = 50))]
+    pub artists_include: Vec<String>,
+    /// Artist names or IDs, none of which may be on the track
+    #[validate(length(max = 50))]
+    pub artists_exclude: Vec<String>,
+    /// Album names, one of which the track must be from
+    #[validate(length(max = 50))]
+    pub albums_include: Vec<String>,
+    /// Album names the track may not be from
+    #[validate(length(max = 50))]
+    pub albums_exclude: Vec<String>,
+    #[validate(range(min = 1000, max = 9999))]
+    pub release_year_min: Option<i32>,
+    #[validate(range(min = 1000, max = 9999))]
+    pub release_year_max: Option<i32>,
+}
+
+impl WatcherRules {
+    /// Whether no rules are set, meaning every track is transferred
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Check a source playlist item against every rule
+    pub fn matches(&self, item: &PlaylistItem, now: DateTime<Utc>) -> bool {
+        let track = &item.track;
+
+        if let Some(days) = self.added_within_days {
+            // Spotify doesn't know when some very old playlist items were added, so leave them be
+            let cutoff = now - chrono::Duration::days(days.into());
+            if item.added_at.is_none_or(|added_at| added_at < cutoff) {
+                return false;
+            }
+        }
+
+        if self.exclude_explicit && track.explicit {
+            return false;
+        }
+
+        let duration_secs = track.duration_ms / 1000;
+        if self.min_duration_secs.is_some_and(|min| duration_secs < min)
+            || self.max_duration_secs.is_some_and(|max| duration_secs > max)
+        {
+            return false;
+        }
+
+        let has_artist = |names: &[String]| {
+            track.artists.iter().any(|artist| {
+                names.iter().any(|name| {
+                    artist.name.eq_ignore_ascii_case(name.trim())
+                        || artist.id.as_ref().is_some_and(|id| {
+                            name.trim() == id.0 || name.trim() == format!("spotify:artist:{}", id.0)
+                        })
+                })
+            })
+        };
+
+        if (!self.artists_include.is_empty() && !has_artist(&self.artists_include))
+            || has_artist(&self.artists_exclude)
+        {
+            return false;
+        }
+
+        let album = track.album.as_ref();
+        let from_album = |names: &[String]| {
+            album.is_some_and(|album| {
+                names.iter().any(|name| album.name.eq_ignore_ascii_case(name.trim()))
+            })
+        };
+
+        if (!self.albums_include.is_empty() && !from_album(&self.albums_include))
+            || from_album(&self.albums_exclude)
+        {
+            return false;
+        }
+
+        if self.release_year_min.is_some() || self.release_year_max.is_some() {
+            let year = album.and_then(|album| album.release_year());
+            if !year.is_some_and(|year| {
+                self.release_year_min.is_none_or(|min| year >= min)
+                    && self.release_year_max.is_none_or(|max| year <= max)
+            }) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn validate_ranges(rules: &WatcherRules) -> Result<(), ValidationError> {
+    if let (Some(min), Some(max)) = (rules.min_duration_secs, rules.max_duration_secs)
+        && min > max
+    {
+        return Err(ValidationError::new("duration_range")
+            .with_message("Minimum duration must not be longer than the maximum.".into()));
+    }
+
+    if let (Some(min), Some(max)) = (rules.release_year_min, rules.release_year_max)
+        && min > max
+    {
+        return Err(ValidationError::new("release_year_range")
+            .with_message("Earliest release year must not be after the latest.".into()));
+    }
+
+    // Years in the future can never match, which is almost certainly a typo
+    if rules.release_year_min.is_some_and(|min| min > Utc::now().year() + 1) {
+        return Err(ValidationError::new("release_year_range")
+            .with_message("Earliest release year is in the future.".into()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::{
+        id::{ArtistId, ItemId, TrackId},
+        model::{AlbumPartial, ArtistPartial, TrackDetails},
+    };
+
+    fn item() -> PlaylistItem {
+        PlaylistItem {
+            id: ItemId::Track(TrackId("track".into())),
+            added_at: Some(Utc::now() - chrono::Duration::days(3)),
+            track: TrackDetails {
+                name: "Song".into(),
+                explicit: true,
+                duration_ms: 200_000,
+                artists: vec![ArtistPartial {
+                    id: Some(ArtistId("artist".into())),
+                    name: "Some Artist".into(),
+                }],
+                album: Some(AlbumPartial {
+                    name: "Some Album".into(),
+                    release_date: Some("2015-06-01".into()),
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn it_matches_everything_without_rules() {
+        assert!(WatcherRules::default().matches(&item(), Utc::now()));
+    }
+
+    #[test]
+    fn it_applies_each_rule() {
+        let test = |rules: WatcherRules| rules.matches(&item(), Utc::now());
+
+        assert!(test(WatcherRules {
+            added_within_days: Some(7),
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            added_within_days: Some(1),
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            exclude_explicit: true,
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            min_duration_secs: Some(240),
+            ..Default::default()
+        }));
+        assert!(test(WatcherRules {
+            min_duration_secs: Some(60),
+            max_duration_secs: Some(240),
+            ..Default::default()
+        }));
+        assert!(test(WatcherRules {
+            artists_include: vec!["some artist".into()],
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            artists_exclude: vec!["spotify:artist:artist".into()],
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            albums_include: vec!["Other Album".into()],
+            ..Default::default()
+        }));
+        assert!(test(WatcherRules {
+            release_year_min: Some(2010),
+            release_year_max: Some(2015),
+            ..Default::default()
+        }));
+        assert!(!test(WatcherRules {
+            release_year_min: Some(2016),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn it_rejects_inverted_ranges() {
+        let rules = WatcherRules {
+            min_duration_secs: Some(300),
+            max_duration_secs: Some(100),
+            ..Default::default()
+        };
+
+        assert!(rules.validate().is_err());
+        assert!(WatcherRules::default().validate().is_ok());
+    }
+}
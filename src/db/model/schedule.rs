@@ -0,0 +1,236 @@
+use crate::db::error::DbError;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Shortest interval a watcher may sync at, since the worker only checks for due watchers every few minutes
+const MIN_INTERVAL_MINS: u32 = 5;
+
+/// Longest interval a watcher may sync at
+const MAX_INTERVAL_MINS: u32 = 365 * 24 * 60;
+
+/// When a watcher syncs. Stored as text, e.g. `every 15m` or `cron 0 6 * * mon`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Schedule {
+    /// Sync a fixed number of minutes after the previous sync
+    Every(u32),
+    /// Sync at the times matching a five-field cron expression, in the user's time zone
+    Cron(String),
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::Every(60)
+    }
+}
+
+impl Schedule {
+    /// Work out when to sync next after a sync at `after`
+    pub fn next_after(&self, after: DateTime<Utc>, time_zone: Tz) -> DateTime<Utc> {
+        match self {
+            Self::Every(mins) => after + chrono::Duration::minutes((*mins).into()),
+            Self::Cron(expression) => parse_cron(expression)
+                .and_then(|cron| {
+                    cron.find_next_occurrence(&after.with_timezone(&time_zone), false).ok()
+                })
+                .map(|next| next.with_timezone(&Utc))
+                // Expressions are checked when parsed, so this only happens if one can never match again
+                .unwrap_or_else(|| after + chrono::Duration::days(1)),
+        }
+    }
+
+    /// Describe the schedule for the dashboard, e.g. "every 3 days"
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Every(mins) => {
+                let (count, unit) = largest_unit(*mins);
+                match count {
+                    1 => format!("every {}", unit_name(unit)),
+                    _ => format!("every {} {}s", count, unit_name(unit)),
+                }
+            }
+            Self::Cron(expression) => format!("on the cron schedule \"{}\"", expression),
+        }
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Every(mins) => {
+                let (count, unit) = largest_unit(*mins);
+                write!(f, "every {}{}", count, unit)
+            }
+            Self::Cron(expression) => write!(f, "cron {}", expression),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_lowercase();
+        let invalid = || DbError::InvalidSchedule(s.to_string());
+
+        let schedule = match value.as_str() {
+            // The fixed intervals watchers had before schedules existed
+            "hour" => Self::Every(60),
+            "day" => Self::Every(24 * 60),
+            "week" => Self::Every(7 * 24 * 60),
+
+            _ => match value.strip_prefix("cron ") {
+                Some(expression) => Self::Cron(expression.trim().to_string()),
+
+                // Bare five-field expressions are treated as cron as well
+                None if value.split_whitespace().count() == 5 => Self::Cron(value.clone()),
+
+                None => {
+                    let captures = Regex::new(r"^every\s+(\d+)?\s*([a-z]+)$")
+                        .expect("regex is valid")
+                        .captures(&value)
+                        .ok_or_else(invalid)?;
+                    let count = captures
+                        .get(1)
+                        .map_or(Ok(1), |count| count.as_str().parse::<u32>())
+                        .map_err(|_| invalid())?;
+                    let unit_mins = match &captures[2] {
+                        "m" | "min" | "mins" | "minute" | "minutes" => 1,
+                        "h" | "hr" | "hrs" | "hour" | "hours" => 60,
+                        "d" | "day" | "days" => 24 * 60,
+                        "w" | "week" | "weeks" => 7 * 24 * 60,
+                        _ => return Err(invalid()),
+                    };
+
+                    Self::Every(count.checked_mul(unit_mins).ok_or_else(invalid)?)
+                }
+            },
+        };
+
+        match &schedule {
+            Self::Every(mins) if !(MIN_INTERVAL_MINS..=MAX_INTERVAL_MINS).contains(mins) => {
+                Err(invalid())
+            }
+            Self::Cron(expression)
+                if parse_cron(expression)
+                    .and_then(|cron| cron.find_next_occurrence(&Utc::now(), false).ok())
+                    .is_none() =>
+            {
+                Err(invalid())
+            }
+            _ => Ok(schedule),
+        }
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = DbError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(value: Schedule) -> Self {
+        value.to_string()
+    }
+}
+
+/// Parse a standard five-field cron expression, where Sunday is day 0
+fn parse_cron(expression: &str) -> Option<Cron> {
+    if expression.split_whitespace().count() != 5 {
+        return None;
+    }
+
+    Cron::new(expression).parse().ok()
+}
+
+/// Split minutes into the largest unit that divides them evenly
+fn largest_unit(mins: u32) -> (u32, &'static str) {
+    [(7 * 24 * 60, "w"), (24 * 60, "d"), (60, "h")]
+        .into_iter()
+        .find(|(unit_mins, _)| mins.is_multiple_of(*unit_mins))
+        .map_or((mins, "m"), |(unit_mins, unit)| (mins / unit_mins, unit))
+}
+
+fn unit_name(unit: &str) -> &'static str {
+    match unit {
+        "w" => "week",
+        "d" => "day",
+        "h" => "hour",
+        _ => "minute",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn it_parses_intervals_and_cron_expressions() {
+        let test = |value: &str, expected: Schedule| {
+            assert_eq!(value.parse::<Schedule>().unwrap(), expected)
+        };
+
+        test("hour", Schedule::Every(60));
+        test("week", Schedule::Every(10080));
+        test("every 15 minutes", Schedule::Every(15));
+        test("Every 3 days", Schedule::Every(3 * 1440));
+        test("every day", Schedule::Every(1440));
+        test("every 90m", Schedule::Every(90));
+        test("cron 0 6 * * mon", Schedule::Cron("0 6 * * mon".into()));
+        test("0 6 * * 1", Schedule::Cron("0 6 * * 1".into()));
+
+        for invalid in [
+            "every 1m",
+            "every 2 years",
+            "fortnightly",
+            "cron 0 6 * *",
+            "cron 0 25 * * *",
+        ] {
+            assert!(
+                invalid.parse::<Schedule>().is_err(),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn it_round_trips_through_storage() {
+        for schedule in [
+            Schedule::Every(15),
+            Schedule::Every(2880),
+            Schedule::Cron("30 6 * * 1-5".into()),
+        ] {
+            assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+        }
+
+        assert_eq!(Schedule::Every(2880).to_string(), "every 2d");
+        assert_eq!(Schedule::Every(2880).describe(), "every 2 days");
+        assert_eq!(Schedule::Every(60).describe(), "every hour");
+    }
+
+    #[test]
+    fn it_finds_the_next_run_in_the_users_time_zone() {
+        // Wednesday 2024-01-03 12:00 UTC
+        let after = Utc.with_ymd_and_hms(2024, 1, 3, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            Schedule::Every(15).next_after(after, Tz::UTC),
+            Utc.with_ymd_and_hms(2024, 1, 3, 12, 15, 0).unwrap()
+        );
+
+        // Mondays at 06:00 in New York is 11:00 UTC in winter
+        assert_eq!(
+            Schedule::Cron("0 6 * * mon".into()).next_after(after, Tz::America__New_York),
+            Utc.with_ymd_and_hms(2024, 1, 8, 11, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{api::id::PlaylistId, db::error::DbError};
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Longest name Spotify accepts for a playlist, which a rendered template has to fit in
+const MAX_NAME_LENGTH: usize = 100;
+
+/// The name of a playlist that changes with each period, such as `Liked {year}-{month}`. Each sync
+/// transfers into the playlist named for the current period, creating it once it has tracks to add.
+///
+/// `{year}`, `{month}`, `{week}` (ISO 8601) and `{day}` are replaced with zero-padded numbers, and the
+/// shortest of them decides how long a period is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PlaylistTemplate(String);
+
+impl PlaylistTemplate {
+    /// The name of the playlist for the period containing a time, in the user's time zone
+    pub fn render(&self, at: DateTime<Utc>, time_zone: Tz) -> String {
+        let date = at.with_timezone(&time_zone).date_naive();
+
+        // Weeks belong to the year their Thursday is in, so the last days of December can be in week 1
+        let year = match self.0.contains("{week}") {
+            true => date.iso_week().year(),
+            false => date.year(),
+        };
+
+        self.0
+            .replace("{year}", &format!("{:04}", year))
+            .replace("{month}", &format!("{:02}", date.month()))
+            .replace("{week}", &format!("{:02}", date.iso_week().week()))
+            .replace("{day}", &format!("{:02}", date.day()))
+    }
+
+    /// When the period containing a time started, in the user's time zone
+    pub fn period_start(&self, at: DateTime<Utc>, time_zone: Tz) -> DateTime<Utc> {
+        let date = at.with_timezone(&time_zone).date_naive();
+
+        let start = if self.0.contains("{day}") {
+            date
+        } else if self.0.contains("{week}") {
+            date - Days::new(date.weekday().num_days_from_monday().into())
+        } else if self.0.contains("{month}") {
+            date.with_day(1).expect("every month has a first day")
+        } else {
+            NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("every year has a first day")
+        };
+
+        let midnight = start.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+
+        // Some zones skip midnight when their clocks change, in which case the period starts at midnight UTC
+        time_zone
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| midnight.and_utc())
+    }
+}
+
+/// The playlist a templated target resolved to for one period, kept so the user's playlists are only
+/// searched for it again once the period ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodPlaylist {
+    pub template: PlaylistTemplate,
+    pub name: String,
+    pub playlist: PlaylistId,
+}
+
+impl Display for PlaylistTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for PlaylistTemplate {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DbError::InvalidPlaylistTemplate(s.to_owned());
+        let placeholders = ["{year}", "{month}", "{week}", "{day}"];
+
+        let mut rest = s;
+        let mut has_placeholder = false;
+        while let Some(start) = rest.find('{') {
+            let placeholder = placeholders
+                .iter()
+                .find(|placeholder| rest[start..].starts_with(*placeholder))
+                .ok_or_else(invalid)?;
+
+            has_placeholder = true;
+            rest = &rest[start + placeholder.len()..];
+        }
+
+        // Without a placeholder every period would share one playlist
+        if !has_placeholder || s.trim().is_empty() || s.len() > MAX_NAME_LENGTH {
+            return Err(invalid());
+        }
+
+        Ok(Self(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn it_renders_names_in_the_users_time_zone() {
+        let template: PlaylistTemplate = "Liked {year}-{month}".parse().unwrap();
+
+        assert_eq!(
+            template.render(at("2026-10-18T12:00:00Z"), Tz::UTC),
+            "Liked 2026-10"
+        );
+        assert_eq!(
+            template.render(at("2026-10-31T23:30:00Z"), Tz::Europe__Berlin),
+            "Liked 2026-11"
+        );
+    }
+
+    #[test]
+    fn it_numbers_weeks_within_their_iso_year() {
+        let template: PlaylistTemplate = "Inbox {year} W{week}".parse().unwrap();
+
+        assert_eq!(
+            template.render(at("2026-12-31T12:00:00Z"), Tz::UTC),
+            "Inbox 2026 W53"
+        );
+        assert_eq!(
+            template.render(at("2027-01-05T12:00:00Z"), Tz::UTC),
+            "Inbox 2027 W01"
+        );
+        assert_eq!(
+            template.render(at("2025-12-29T12:00:00Z"), Tz::UTC),
+            "Inbox 2026 W01"
+        );
+    }
+
+    #[test]
+    fn it_starts_periods_at_local_midnight() {
+        let now = at("2026-10-18T12:00:00Z");
+        let start = |template: &str, time_zone| {
+            template.parse::<PlaylistTemplate>().unwrap().period_start(now, time_zone)
+        };
+
+        assert_eq!(start("{year}", Tz::UTC), at("2026-01-01T00:00:00Z"));
+        assert_eq!(start("{year}-{month}", Tz::UTC), at("2026-10-01T00:00:00Z"));
+        assert_eq!(start("{year} W{week}", Tz::UTC), at("2026-10-12T00:00:00Z"));
+        assert_eq!(start("{month}/{day}", Tz::UTC), at("2026-10-18T00:00:00Z"));
+        assert_eq!(
+            start("{year}-{month}", Tz::Europe__Berlin),
+            at("2026-09-30T22:00:00Z")
+        );
+    }
+
+    #[test]
+    fn it_rejects_templates_without_known_placeholders() {
+        for template in [
+            "Liked",
+            "Liked {yaer}",
+            "{year",
+            "",
+            &format!("{{year}}{}", "a".repeat(100)),
+        ] {
+            assert!(
+                template.parse::<PlaylistTemplate>().is_err(),
+                "{}",
+                template
+            );
+        }
+    }
+}
@@ -1,18 +1,22 @@
 use crate::db::error::DbError;
 use chrono::{DateTime, Utc};
 use r2d2_sqlite::rusqlite::Row;
+use serde::Serialize;
 
-#[allow(dead_code)]
-pub const COLUMNS: &str = "id, watcher_id, num_tracks_transferred,error, synced_at, created_at";
+pub const COLUMNS: &str = "id, watcher_id, num_tracks_transferred, error, synced_at, created_at, undo_of, num_items_skipped";
 
-#[allow(unused)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Transfer {
     pub id: u32,
     pub watcher_id: u32,
     pub num_tracks_transferred: u32,
     pub error: Option<String>,
+    pub synced_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    /// The transfer this one reverted, if it was an undo
+    pub undo_of: Option<u32>,
+    /// Local files left behind because they can't be transferred
+    pub num_items_skipped: u32,
 }
 
 impl TryFrom<&Row<'_>> for Transfer {
@@ -24,7 +28,10 @@ impl TryFrom<&Row<'_>> for Transfer {
             watcher_id: row.get(1)?,
             num_tracks_transferred: row.get(2)?,
             error: row.get(3)?,
-            created_at: row.get::<_, String>(4)?.parse()?,
+            synced_at: row.get::<_, String>(4)?.parse()?,
+            created_at: row.get::<_, String>(5)?.parse()?,
+            undo_of: row.get(6)?,
+            num_items_skipped: row.get(7)?,
         })
     }
 }
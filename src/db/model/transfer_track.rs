@@ -0,0 +1,103 @@
+use super::playlist::PlaylistType;
+use crate::{api::id::ItemId, db::error::DbError};
+use chrono::{DateTime, Utc};
+use r2d2_sqlite::rusqlite::Row;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+pub const COLUMNS: &str = "id, transfer_id, track_id, action, playlist_from, playlist_to, position, created_at, item_type";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferTrack {
+    pub id: u32,
+    pub transfer_id: u32,
+    pub item_id: ItemId,
+    pub action: TrackAction,
+    pub playlist_from: PlaylistType,
+    pub playlist_to: PlaylistType,
+    pub position: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row<'_>> for TransferTrack {
+    type Error = DbError;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.get(0)?,
+            transfer_id: row.get(1)?,
+            item_id: {
+                let kind = row.get::<_, String>(8)?;
+                ItemId::from_parts(&kind, row.get(2)?).ok_or(DbError::InvalidItemType(kind))?
+            },
+            action: row.get::<_, String>(3)?.parse()?,
+            playlist_from: PlaylistType::try_from_value(&row.get::<_, String>(4)?)?,
+            playlist_to: PlaylistType::try_from_value(&row.get::<_, String>(5)?)?,
+            position: row.get(6)?,
+            created_at: row.get::<_, String>(7)?.parse()?,
+        })
+    }
+}
+
+/// A change made to a single track during a transfer, before it has been saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackChange {
+    pub item_id: ItemId,
+    pub action: TrackAction,
+    pub playlist_from: PlaylistType,
+    pub playlist_to: PlaylistType,
+    /// Index in `playlist_to` for added tracks, otherwise the index in `playlist_from`
+    pub position: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackAction {
+    Added,
+    Removed,
+    SkippedDuplicate,
+    /// Local files can't be added through the API, so they stay where they are
+    SkippedLocal,
+    /// Liked Tracks can only hold tracks, so episodes bound for it stay where they are
+    SkippedUnsupported,
+    /// Removed from a full target to make room for new tracks, and moved to the overflow playlist if
+    /// the watcher has one
+    Evicted,
+}
+
+impl TrackAction {
+    /// Whether the item was left where it is because it can't be transferred at all, which happens on
+    /// every sync until it is moved by hand
+    pub fn is_untransferable(&self) -> bool {
+        matches!(self, Self::SkippedLocal | Self::SkippedUnsupported)
+    }
+}
+
+impl Display for TrackAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::Added => write!(f, "added"),
+            Self::Removed => write!(f, "removed"),
+            Self::SkippedDuplicate => write!(f, "skipped_duplicate"),
+            Self::SkippedLocal => write!(f, "skipped_local"),
+            Self::SkippedUnsupported => write!(f, "skipped_unsupported"),
+            Self::Evicted => write!(f, "evicted"),
+        }
+    }
+}
+
+impl FromStr for TrackAction {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "added" => TrackAction::Added,
+            "removed" => TrackAction::Removed,
+            "skipped_duplicate" => TrackAction::SkippedDuplicate,
+            "skipped_local" => TrackAction::SkippedLocal,
+            "skipped_unsupported" => TrackAction::SkippedUnsupported,
+            "evicted" => TrackAction::Evicted,
+            _ => return Err(DbError::InvalidTrackAction(s.to_string())),
+        })
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{api::token::Token, db::error::DbError};
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use r2d2_sqlite::rusqlite::Row;
 
-pub const COLUMNS: &str = "id, user_uri, token, created_at";
+pub const COLUMNS: &str = "id, user_uri, token, created_at, time_zone";
 
 #[allow(unused)]
 #[derive(Debug, Clone)]
@@ -11,6 +12,8 @@ pub struct User {
     pub user_uri: String,
     pub token: Token,
     pub created_at: DateTime<Utc>,
+    /// Cron schedules of the user's watchers are evaluated in this time zone
+    pub time_zone: Tz,
 }
 
 impl TryFrom<&Row<'_>> for User {
@@ -22,6 +25,10 @@ impl TryFrom<&Row<'_>> for User {
             user_uri: row.get(1)?,
             token: serde_json::from_str(&row.get::<_, String>(2)?)?,
             created_at: row.get::<_, String>(3)?.parse()?,
+            time_zone: {
+                let time_zone = row.get::<_, String>(4)?;
+                time_zone.parse().map_err(|_| DbError::InvalidTimeZone(time_zone))?
+            },
         })
     }
 }
@@ -1,11 +1,12 @@
-use super::playlist::PlaylistType;
+use super::{
+    options::WatcherOptions, playlist::PlaylistType, rules::WatcherRules, schedule::Schedule,
+    template::PeriodPlaylist,
+};
 use crate::db::error::DbError;
 use chrono::{DateTime, Utc};
 use r2d2_sqlite::rusqlite::Row;
-use serde::{Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
 
-pub const COLUMNS: &str = "id, user_uri, playlist_from, playlist_to, should_remove, sync_interval, last_sync_at, next_sync_at, created_at";
+pub const COLUMNS: &str = "id, user_uri, playlist_from, playlist_to, should_remove, schedule, last_sync_at, next_sync_at, created_at, rules, enabled, paused_reason, consecutive_failures, options, source_snapshot, target_snapshot, extra_sources, extra_targets, period_playlists";
 
 #[allow(unused)]
 #[derive(Debug, Clone)]
@@ -15,10 +16,75 @@ pub struct Watcher {
     pub playlist_from: PlaylistType,
     pub playlist_to: PlaylistType,
     pub should_remove: bool,
-    pub sync_interval: SyncInterval,
+    pub schedule: Schedule,
     pub last_sync_at: Option<DateTime<Utc>>,
     pub next_sync_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub rules: WatcherRules,
+    /// Paused watchers are skipped by the sync worker until they are resumed
+    pub enabled: bool,
+    /// Why the watcher was paused, if the worker paused it
+    pub paused_reason: Option<String>,
+    pub consecutive_failures: u32,
+    pub options: WatcherOptions,
+    /// The state of both playlists after the last successful sync, if it is known
+    pub snapshots: Option<PlaylistSnapshots>,
+    /// Further playlists whose tracks are merged with those of `playlist_from`
+    pub extra_sources: Vec<PlaylistType>,
+    /// Further playlists that get the same tracks as `playlist_to`
+    pub extra_targets: Vec<PlaylistType>,
+    /// The playlists the watcher's templated targets resolved to at its last sync
+    pub period_playlists: Vec<PeriodPlaylist>,
+}
+
+impl Watcher {
+    /// Every playlist the watcher transfers from, starting with `playlist_from`
+    pub fn sources(&self) -> impl Iterator<Item = &PlaylistType> {
+        std::iter::once(&self.playlist_from).chain(&self.extra_sources)
+    }
+
+    /// Every playlist the watcher transfers to, starting with `playlist_to`
+    pub fn targets(&self) -> impl Iterator<Item = &PlaylistType> {
+        std::iter::once(&self.playlist_to).chain(&self.extra_targets)
+    }
+}
+
+/// The settings a watcher is created with. Everything but its user and playlists has a default.
+#[derive(Debug, Clone)]
+pub struct NewWatcher {
+    pub user_uri: String,
+    pub playlist_from: PlaylistType,
+    pub playlist_to: PlaylistType,
+    pub should_remove: bool,
+    pub schedule: Schedule,
+    pub rules: WatcherRules,
+    pub options: WatcherOptions,
+    pub extra_sources: Vec<PlaylistType>,
+    pub extra_targets: Vec<PlaylistType>,
+}
+
+impl NewWatcher {
+    pub fn new(user_uri: &str, playlist_from: PlaylistType, playlist_to: PlaylistType) -> Self {
+        Self {
+            user_uri: user_uri.into(),
+            playlist_from,
+            playlist_to,
+            should_remove: false,
+            schedule: Schedule::default(),
+            rules: WatcherRules::default(),
+            options: WatcherOptions::default(),
+            extra_sources: vec![],
+            extra_targets: vec![],
+        }
+    }
+}
+
+/// Fingerprints of the source and target playlists, which change whenever their tracks do. Watchers
+/// with more than one source or target combine theirs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistSnapshots {
+    pub source: String,
+    pub target: String,
 }
 
 impl TryFrom<&Row<'_>> for Watcher {
@@ -31,52 +97,34 @@ impl TryFrom<&Row<'_>> for Watcher {
             playlist_from: PlaylistType::try_from_value(&row.get::<_, String>(2)?)?,
             playlist_to: PlaylistType::try_from_value(&row.get::<_, String>(3)?)?,
             should_remove: row.get(4)?,
-            sync_interval: row.get::<_, String>(5)?.parse()?,
+            schedule: row.get::<_, String>(5)?.parse()?,
             last_sync_at: row.get::<_, Option<String>>(6)?.and_then(|val| val.parse().ok()),
             next_sync_at: row.get::<_, Option<String>>(7)?.and_then(|val| val.parse().ok()),
             created_at: row.get::<_, String>(8)?.parse()?,
+            rules: row
+                .get::<_, Option<String>>(9)?
+                .map(|rules| serde_json::from_str(&rules))
+                .transpose()?
+                .unwrap_or_default(),
+            enabled: row.get(10)?,
+            paused_reason: row.get(11)?,
+            consecutive_failures: row.get(12)?,
+            options: row
+                .get::<_, Option<String>>(13)?
+                .map(|options| serde_json::from_str(&options))
+                .transpose()?
+                .unwrap_or_default(),
+            snapshots: match (row.get(14)?, row.get(15)?) {
+                (Some(source), Some(target)) => Some(PlaylistSnapshots { source, target }),
+                _ => None,
+            },
+            extra_sources: PlaylistType::try_list_from_value(row.get(16)?)?,
+            extra_targets: PlaylistType::try_list_from_value(row.get(17)?)?,
+            period_playlists: row
+                .get::<_, Option<String>>(18)?
+                .map(|periods| serde_json::from_str(&periods))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
-
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "lowercase")]
-pub enum SyncInterval {
-    #[default]
-    Hour,
-    Day,
-    Week,
-}
-
-impl Display for SyncInterval {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self {
-            Self::Hour => write!(f, "hour"),
-            Self::Day => write!(f, "day"),
-            Self::Week => write!(f, "week"),
-        }
-    }
-}
-
-impl FromStr for SyncInterval {
-    type Err = DbError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "hour" => SyncInterval::Hour,
-            "day" => SyncInterval::Day,
-            "week" => SyncInterval::Week,
-            _ => return Err(DbError::InvalidSyncInterval(s.to_string())),
-        })
-    }
-}
-
-impl From<SyncInterval> for chrono::Duration {
-    fn from(value: SyncInterval) -> Self {
-        match value {
-            SyncInterval::Hour => chrono::Duration::try_hours(1).expect("won't overflow"),
-            SyncInterval::Day => chrono::Duration::try_days(1).expect("won't overflow"),
-            SyncInterval::Week => chrono::Duration::try_weeks(1).expect("won't overflow"),
-        }
-    }
-}
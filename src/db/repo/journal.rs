@@ -0,0 +1,125 @@
+use crate::db::{
+    error::DbResult,
+    model::{
+        journal::{BATCH_COLUMNS, BatchStatus, COLUMNS, Journal, JournalBatch, PlannedBatch},
+        transfer_track::TrackChange,
+    },
+};
+use chrono::{DateTime, Utc};
+use r2d2_sqlite::rusqlite::{Transaction, params};
+
+pub struct JournalRepo {
+    ctx: crate::context::AppContext,
+}
+
+impl JournalRepo {
+    pub fn new(ctx: crate::context::AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Write a transfer's plan and the batches that carry it out before any of them are sent
+    pub fn create_journal(
+        &self,
+        watcher_id: u32,
+        synced_at: DateTime<Utc>,
+        plan: &[TrackChange],
+        batches: &[PlannedBatch],
+    ) -> DbResult<u32> {
+        let mut conn = self.ctx.db.get()?;
+        let tx = conn.transaction()?;
+
+        tx.prepare(
+            "INSERT INTO transfer_journals (watcher_id, plan, synced_at, created_at) VALUES (?1, ?2, ?3, ?4)",
+        )?
+        .execute(params![
+            watcher_id,
+            serde_json::to_string(plan)?,
+            synced_at.to_rfc3339(),
+            Utc::now().to_rfc3339()
+        ])?;
+
+        let journal_id = tx.last_insert_rowid();
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO transfer_journal_batches (journal_id, action, playlist, items, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+
+            for batch in batches {
+                stmt.execute(params![
+                    journal_id,
+                    batch.action.to_string(),
+                    batch.playlist.to_value(),
+                    serde_json::to_string(&batch.items)?,
+                    batch.position
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(journal_id.try_into().expect("IDs are positive"))
+    }
+
+    /// Get the journals of transfers created before a time that never finished, oldest first.
+    pub fn get_unfinished_journals(&self, before: DateTime<Utc>) -> DbResult<Vec<Journal>> {
+        // Times are all stored in UTC as RFC 3339, so comparing them as text orders them correctly
+        self.ctx
+            .db
+            .get()?
+            .prepare(
+                format!(
+                    "SELECT {COLUMNS} FROM transfer_journals WHERE created_at < ?1 ORDER BY id"
+                )
+                .as_ref(),
+            )?
+            .query_and_then(params![before.to_rfc3339()], |row| Journal::try_from(row))?
+            .collect::<DbResult<Vec<_>>>()
+    }
+
+    /// Get the batches of a journal in the order they are sent.
+    pub fn get_batches(&self, journal_id: u32) -> DbResult<Vec<JournalBatch>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(
+                format!(
+                    "SELECT {BATCH_COLUMNS} FROM transfer_journal_batches WHERE journal_id = ?1 ORDER BY id"
+                )
+                .as_ref(),
+            )?
+            .query_and_then(params![journal_id], |row| JournalBatch::try_from(row))?
+            .collect::<DbResult<Vec<_>>>()
+    }
+
+    /// Record how far a batch has got.
+    pub fn update_batch_status(&self, id: u32, status: BatchStatus) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE transfer_journal_batches SET status = ?1 WHERE id = ?2")?
+            .execute(params![status.to_string(), id])?;
+
+        Ok(())
+    }
+
+    /// Delete a journal and its batches.
+    pub fn delete_journal(&self, id: u32) -> DbResult<()> {
+        let mut conn = self.ctx.db.get()?;
+        let tx = conn.transaction()?;
+        delete_journal(&tx, id)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Delete a journal and its batches as part of another transaction
+pub fn delete_journal(tx: &Transaction, id: u32) -> DbResult<()> {
+    tx.prepare("DELETE FROM transfer_journal_batches WHERE journal_id = ?1")?
+        .execute(params![id])?;
+    tx.prepare("DELETE FROM transfer_journals WHERE id = ?1")?
+        .execute(params![id])?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::db::{
+    error::DbResult,
+    model::mirror::{COLUMNS, MirrorItem, MirrorState},
+};
+use chrono::Utc;
+use r2d2_sqlite::rusqlite::params;
+
+pub struct MirrorRepo {
+    ctx: crate::context::AppContext,
+}
+
+impl MirrorRepo {
+    pub fn new(ctx: crate::context::AppContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Get the state a mirroring watcher left its playlists in, if it has synced since it started mirroring.
+    pub fn get_state(&self, watcher_id: u32) -> DbResult<Option<MirrorState>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(format!("SELECT {COLUMNS} FROM mirror_states WHERE watcher_id = ?1").as_ref())?
+            .query_and_then(params![watcher_id], |row| MirrorState::try_from(row))?
+            .next()
+            .transpose()
+    }
+
+    /// Replace the state of a mirroring watcher after it has synced.
+    pub fn save_state(&self, watcher_id: u32, items: &[MirrorItem]) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("INSERT INTO mirror_states (watcher_id, items, updated_at) VALUES (?1, ?2, ?3) ON CONFLICT (watcher_id) DO UPDATE SET items = excluded.items, updated_at = excluded.updated_at")?
+            .execute(params![watcher_id, serde_json::to_string(items)?, Utc::now().to_rfc3339()])?;
+
+        Ok(())
+    }
+
+    /// Forget the state of a watcher, so its next sync treats every difference as a conflict.
+    pub fn delete_state(&self, watcher_id: u32) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("DELETE FROM mirror_states WHERE watcher_id = ?1")?
+            .execute(params![watcher_id])?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+pub mod journal;
+pub mod mirror;
 pub mod transfer;
 pub mod user;
 pub mod watcher;
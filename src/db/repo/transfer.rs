@@ -1,12 +1,19 @@
 use crate::{
+    api::id::ItemId,
     db::{
-        error::DbResult,
-        model::transfer::{COLUMNS, Transfer},
+        error::{DbError, DbResult},
+        model::{
+            playlist::PlaylistType,
+            transfer::{COLUMNS, Transfer},
+            transfer_track::{self, TrackAction, TrackChange, TransferTrack},
+        },
+        repo::journal,
     },
     sync::error::SyncError,
 };
 use chrono::{DateTime, Utc};
-use r2d2_sqlite::rusqlite::params;
+use r2d2_sqlite::rusqlite::{Transaction, params};
+use std::collections::HashMap;
 
 pub struct TransferRepo {
     ctx: crate::context::AppContext,
@@ -17,39 +24,239 @@ impl TransferRepo {
         Self { ctx }
     }
 
-    /// Create a transfer record with a list of errors
+    /// Create a transfer record along with the track changes it made
     pub fn log_transfer(
         &self,
         watcher_id: u32,
         num_tracks_transferred: &u32,
         error: &Option<&SyncError>,
         synced_at: DateTime<Utc>,
+        tracks: &[TrackChange],
+        undo_of: Option<u32>,
     ) -> DbResult<()> {
+        let mut conn = self.ctx.db.get()?;
+        let tx = conn.transaction()?;
+        insert_transfer(
+            &tx,
+            watcher_id,
+            num_tracks_transferred,
+            error,
+            synced_at,
+            tracks,
+            undo_of,
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Create a transfer record for a journaled transfer and delete its journal, so that the transfer
+    /// can't be logged twice or resumed once it has been logged
+    pub fn log_journaled_transfer(
+        &self,
+        journal_id: u32,
+        watcher_id: u32,
+        num_tracks_transferred: &u32,
+        error: &Option<&SyncError>,
+        synced_at: DateTime<Utc>,
+        tracks: &[TrackChange],
+    ) -> DbResult<()> {
+        let mut conn = self.ctx.db.get()?;
+        let tx = conn.transaction()?;
+        insert_transfer(
+            &tx,
+            watcher_id,
+            num_tracks_transferred,
+            error,
+            synced_at,
+            tracks,
+            None,
+        )?;
+        journal::delete_journal(&tx, journal_id)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Get a transfer by ID.
+    pub fn get_transfer_by_id(&self, id: u32) -> DbResult<Option<Transfer>> {
         self.ctx
+            .db
+            .get()?
+            .prepare(format!("SELECT {COLUMNS} FROM transfers WHERE transfers.id = ?1").as_ref())?
+            .query_and_then(params![id], |row| Transfer::try_from(row))?
+            .next()
+            .transpose()
+    }
+
+    /// Get the transfer that undid a transfer by ID, if it has been undone.
+    pub fn get_undo_of_transfer(&self, id: u32) -> DbResult<Option<Transfer>> {
+        self
+            .ctx
             .db
             .get()?
             .prepare(
-                "INSERT INTO transfers (watcher_id, num_tracks_transferred, error, synced_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                format!("SELECT {COLUMNS} FROM transfers WHERE transfers.undo_of = ?1 AND transfers.error IS NULL")
+                    .as_ref(),
             )?
-            .execute(params![
-                watcher_id,
-                num_tracks_transferred,
-                error.map(|err| err.to_string()).unwrap_or_default(),
-                synced_at.to_rfc3339(),
-                chrono::Utc::now().to_rfc3339()
-            ])?;
+            .query_and_then(params![id], |row| Transfer::try_from(row))?
+            .next()
+            .transpose()
+    }
 
-        Ok(())
+    /// Fetch the track changes made by a transfer by ID.
+    pub fn get_tracks_for_transfer(&self, transfer_id: u32) -> DbResult<Vec<TransferTrack>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(
+                format!(
+                    "SELECT {} FROM transfer_tracks WHERE transfer_tracks.transfer_id = ?1 ORDER BY transfer_tracks.id",
+                    transfer_track::COLUMNS
+                )
+                .as_ref(),
+            )?
+            .query_and_then(params![transfer_id], |row| {
+                TransferTrack::try_from(row)
+            })?
+            .collect::<DbResult<Vec<_>>>()
     }
 
-    /// Fetch all transfers for a watcher by ID.
-    #[allow(dead_code)]
-    pub fn get_transfers_for_watcher(&self, id: u32) -> DbResult<Vec<Transfer>> {
+    /// Search a user's transfer history for changes to an item, newest first.
+    pub fn get_track_history_for_user(
+        &self,
+        user_uri: &str,
+        item_id: &ItemId,
+        limit: u32,
+        offset: u32,
+    ) -> DbResult<Vec<TransferTrack>> {
+        let columns = transfer_track::COLUMNS
+            .split(", ")
+            .map(|column| format!("transfer_tracks.{column}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
         self.ctx
             .db
             .get()?
-            .prepare(format!("SELECT {COLUMNS} FROM transfers WHERE transfers.id = ?1").as_ref())?
-            .query_and_then(params![id], |row| Transfer::try_from(row))?
+            .prepare(
+                format!(
+                    "SELECT {columns} FROM transfer_tracks
+                        INNER JOIN transfers ON transfers.id = transfer_tracks.transfer_id
+                        INNER JOIN watchers ON watchers.id = transfers.watcher_id
+                        WHERE watchers.user_uri = ?1 AND transfer_tracks.track_id = ?2 AND transfer_tracks.item_type = ?3
+                        ORDER BY transfer_tracks.id DESC LIMIT ?4 OFFSET ?5"
+                )
+                .as_ref(),
+            )?
+            .query_and_then(params![user_uri, item_id.id(), item_id.kind(), limit, offset], |row| {
+                TransferTrack::try_from(row)
+            })?
             .collect::<DbResult<Vec<_>>>()
     }
+
+    /// Find when a watcher by ID last evicted each item it has evicted from a playlist.
+    pub fn get_eviction_times(
+        &self,
+        watcher_id: u32,
+        playlist: &PlaylistType,
+    ) -> DbResult<HashMap<ItemId, DateTime<Utc>>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(
+                "SELECT transfer_tracks.item_type, transfer_tracks.track_id, MAX(transfers.synced_at) FROM transfer_tracks
+                    INNER JOIN transfers ON transfers.id = transfer_tracks.transfer_id
+                    WHERE transfers.watcher_id = ?1 AND transfer_tracks.action = ?2 AND transfer_tracks.playlist_from = ?3
+                    GROUP BY transfer_tracks.item_type, transfer_tracks.track_id",
+            )?
+            .query_and_then(
+                params![watcher_id, TrackAction::Evicted.to_string(), playlist.to_value()],
+                |row| {
+                    let kind = row.get::<_, String>(0)?;
+                    let item_id = ItemId::from_parts(&kind, row.get(1)?)
+                        .ok_or(DbError::InvalidItemType(kind))?;
+                    Ok((item_id, row.get::<_, String>(2)?.parse()?))
+                },
+            )?
+            .collect()
+    }
+
+    /// Fetch a page of transfers for a watcher by ID, newest first.
+    pub fn get_transfers_for_watcher(
+        &self,
+        watcher_id: u32,
+        limit: u32,
+        offset: u32,
+    ) -> DbResult<Vec<Transfer>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(
+                format!("SELECT {COLUMNS} FROM transfers WHERE transfers.watcher_id = ?1 ORDER BY transfers.id DESC LIMIT ?2 OFFSET ?3")
+                    .as_ref(),
+            )?
+            .query_and_then(params![watcher_id, limit, offset], |row| {
+                Transfer::try_from(row)
+            })?
+            .collect::<DbResult<Vec<_>>>()
+    }
+
+    /// Count all transfers for a watcher by ID.
+    pub fn count_transfers_for_watcher(&self, watcher_id: u32) -> DbResult<u32> {
+        Ok(self
+            .ctx
+            .db
+            .get()?
+            .prepare("SELECT COUNT(*) FROM transfers WHERE transfers.watcher_id = ?1")?
+            .query_row(params![watcher_id], |row| row.get(0))?)
+    }
+}
+
+/// Insert a transfer record and its track changes as part of a transaction
+fn insert_transfer(
+    tx: &Transaction,
+    watcher_id: u32,
+    num_tracks_transferred: &u32,
+    error: &Option<&SyncError>,
+    synced_at: DateTime<Utc>,
+    tracks: &[TrackChange],
+    undo_of: Option<u32>,
+) -> DbResult<()> {
+    let created_at = chrono::Utc::now().to_rfc3339();
+    let num_items_skipped =
+        tracks.iter().filter(|track| track.action.is_untransferable()).count() as u32;
+
+    tx.prepare(
+        "INSERT INTO transfers (watcher_id, num_tracks_transferred, error, synced_at, created_at, undo_of, num_items_skipped) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?
+    .execute(params![
+        watcher_id,
+        num_tracks_transferred,
+        error.map(|err| err.to_string()),
+        synced_at.to_rfc3339(),
+        created_at,
+        undo_of,
+        num_items_skipped
+    ])?;
+
+    let transfer_id = tx.last_insert_rowid();
+    let mut stmt = tx.prepare(
+        "INSERT INTO transfer_tracks (transfer_id, track_id, item_type, action, playlist_from, playlist_to, position, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+
+    for track in tracks {
+        stmt.execute(params![
+            transfer_id,
+            track.item_id.id(),
+            track.item_id.kind(),
+            track.action.to_string(),
+            track.playlist_from.to_value(),
+            track.playlist_to.to_value(),
+            track.position,
+            created_at
+        ])?;
+    }
+
+    Ok(())
 }
@@ -5,6 +5,7 @@ use crate::{
         model::user::{COLUMNS, User},
     },
 };
+use chrono_tz::Tz;
 use r2d2_sqlite::rusqlite::params;
 
 pub struct UserRepo {
@@ -22,7 +23,7 @@ impl UserRepo {
             .db
             .get()?
             .prepare(
-                &format!("INSERT OR REPLACE INTO users (user_uri, token, created_at) VALUES (?1, ?2, ?3) RETURNING {COLUMNS}"),
+                &format!("INSERT INTO users (user_uri, token, created_at) VALUES (?1, ?2, ?3) ON CONFLICT (user_uri) DO UPDATE SET token = excluded.token RETURNING {COLUMNS}"),
             )?
             .query_and_then(
                 params![
@@ -51,6 +52,17 @@ impl UserRepo {
             .cloned())
     }
 
+    /// Set the time zone a user's cron schedules are evaluated in
+    pub fn update_user_time_zone(&self, user_uri: &str, time_zone: Tz) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE users SET time_zone = ?1 WHERE user_uri = ?2")?
+            .execute(params![time_zone.name(), user_uri])?;
+
+        Ok(())
+    }
+
     /// Delete a user by their Spotify URI
     pub fn delete_user_by_uri(&self, user_uri: &str) -> DbResult<()> {
         self.ctx
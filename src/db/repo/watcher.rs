@@ -1,8 +1,12 @@
 use crate::db::{
     error::{DbError, DbResult},
     model::{
+        options::WatcherOptions,
         playlist::PlaylistType,
-        watcher::{COLUMNS, SyncInterval, Watcher},
+        rules::WatcherRules,
+        schedule::Schedule,
+        template::PeriodPlaylist,
+        watcher::{COLUMNS, NewWatcher, PlaylistSnapshots, Watcher},
     },
 };
 use chrono::Utc;
@@ -27,13 +31,14 @@ impl WatcherRepo {
             .collect::<DbResult<Vec<_>>>()
     }
 
-    /// Get all watchers for a specific playlist.
+    /// Get all watchers that transfer from a specific playlist, as their main or an extra source, or
+    /// that mirror it.
     pub fn get_watchers_for_playlist(&self, from: &PlaylistType) -> DbResult<Vec<Watcher>> {
         self.ctx
             .db
             .get()?
             .prepare(
-                format!("SELECT {COLUMNS} FROM watchers WHERE watchers.playlist_from = ?1")
+                format!("SELECT {COLUMNS} FROM watchers WHERE watchers.playlist_from = ?1 OR EXISTS (SELECT 1 FROM json_each(watchers.extra_sources) WHERE json_each.value = ?1) OR (watchers.playlist_to = ?1 AND json_extract(watchers.options, '$.mirror') = 1)")
                     .as_ref(),
             )?
             .query_and_then(params![from.to_value()], |row| row.try_into())?
@@ -52,6 +57,17 @@ impl WatcherRepo {
             .collect::<DbResult<Vec<_>>>()
     }
 
+    /// Get a watcher by ID, regardless of which user owns it.
+    pub fn get_watcher_by_id(&self, id: u32) -> DbResult<Option<Watcher>> {
+        self.ctx
+            .db
+            .get()?
+            .prepare(format!("SELECT {COLUMNS} FROM watchers WHERE watchers.id = ?1").as_ref())?
+            .query_and_then(params![id], |row| Watcher::try_from(row))?
+            .next()
+            .transpose()
+    }
+
     /// Get specific watcher for a given ID and user URI.
     pub fn get_watcher_by_id_and_user(&self, id: u32, user_uri: &str) -> DbResult<Option<Watcher>> {
         let rows = self
@@ -107,19 +123,134 @@ impl WatcherRepo {
     }
 
     /// Create a watcher for a user and playlist.
-    pub fn create_watcher(
+    pub fn create_watcher(&self, watcher: &NewWatcher) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("INSERT INTO watchers (user_uri, playlist_from, playlist_to, should_remove, schedule, created_at, rules, options, extra_sources, extra_targets) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?
+            .execute(params![watcher.user_uri, watcher.playlist_from.to_value(), watcher.playlist_to.to_value(), watcher.should_remove, watcher.schedule.to_string(), Utc::now().to_rfc3339(), rules_to_value(&watcher.rules)?, options_to_value(&watcher.options)?, PlaylistType::list_to_value(&watcher.extra_sources)?, PlaylistType::list_to_value(&watcher.extra_targets)?])?;
+
+        Ok(())
+    }
+
+    /// Remember the state of a watcher's playlists after a sync by ID.
+    pub fn update_watcher_snapshots(&self, id: u32, snapshots: &PlaylistSnapshots) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET source_snapshot = ?1, target_snapshot = ?2 WHERE watchers.id = ?3")?
+            .execute(params![snapshots.source, snapshots.target, id])?;
+
+        Ok(())
+    }
+
+    /// Remember the playlists a watcher's templated targets resolved to by ID.
+    pub fn update_watcher_period_playlists(
         &self,
-        user_uri: &str,
-        from: &PlaylistType,
+        id: u32,
+        periods: &[PeriodPlaylist],
+    ) -> DbResult<()> {
+        let periods = (!periods.is_empty()).then(|| serde_json::to_string(periods)).transpose()?;
+
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET period_playlists = ?1 WHERE watchers.id = ?2")?
+            .execute(params![periods, id])?;
+
+        Ok(())
+    }
+
+    /// Update the editable settings of a watcher by ID. Its snapshots are forgotten, so the next sync
+    /// reads both playlists in full.
+    pub fn update_watcher(
+        &self,
+        id: u32,
         to: &PlaylistType,
         should_remove: bool,
-        sync_interval: SyncInterval,
+        schedule: &Schedule,
+        next_sync_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET playlist_to = ?1, should_remove = ?2, schedule = ?3, next_sync_at = ?4, source_snapshot = NULL, target_snapshot = NULL WHERE watchers.id = ?5")?
+            .execute(params![to.to_value(), should_remove, schedule.to_string(), next_sync_at.map(|at| at.to_rfc3339()), id])?;
+
+        Ok(())
+    }
+
+    /// Replace the extra sources and targets of a watcher by ID, forgetting its snapshots.
+    pub fn update_watcher_extra_playlists(
+        &self,
+        id: u32,
+        extra_sources: &[PlaylistType],
+        extra_targets: &[PlaylistType],
+    ) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET extra_sources = ?1, extra_targets = ?2, source_snapshot = NULL, target_snapshot = NULL WHERE watchers.id = ?3")?
+            .execute(params![PlaylistType::list_to_value(extra_sources)?, PlaylistType::list_to_value(extra_targets)?, id])?;
+
+        Ok(())
+    }
+
+    /// Pause or resume a watcher by ID. Resuming clears the failure count so it gets a fresh start.
+    pub fn update_watcher_enabled(
+        &self,
+        id: u32,
+        enabled: bool,
+        paused_reason: Option<&str>,
     ) -> DbResult<()> {
         self.ctx
             .db
             .get()?
-            .prepare("INSERT INTO watchers (user_uri, playlist_from, playlist_to, should_remove, sync_interval, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?
-            .execute(params![user_uri, from.to_value(), to.to_value(), should_remove, sync_interval.to_string(), Utc::now().to_rfc3339()])?;
+            .prepare("UPDATE watchers SET enabled = ?1, paused_reason = ?2, consecutive_failures = CASE WHEN ?1 THEN 0 ELSE consecutive_failures END WHERE watchers.id = ?3")?
+            .execute(params![enabled, paused_reason, id])?;
+
+        Ok(())
+    }
+
+    /// Count another failed sync for a watcher by ID, returning how many have failed in a row.
+    pub fn increment_watcher_failures(&self, id: u32) -> DbResult<u32> {
+        Ok(self
+            .ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET consecutive_failures = consecutive_failures + 1 WHERE watchers.id = ?1 RETURNING consecutive_failures")?
+            .query_row(params![id], |row| row.get(0))?)
+    }
+
+    /// Forget previous failures of a watcher by ID after it synced successfully.
+    pub fn reset_watcher_failures(&self, id: u32) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET consecutive_failures = 0 WHERE watchers.id = ?1")?
+            .execute(params![id])?;
+
+        Ok(())
+    }
+
+    /// Replace the filter rules of a watcher by ID, forgetting its snapshots.
+    pub fn update_watcher_rules(&self, id: u32, rules: &WatcherRules) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET rules = ?1, source_snapshot = NULL, target_snapshot = NULL WHERE watchers.id = ?2")?
+            .execute(params![rules_to_value(rules)?, id])?;
+
+        Ok(())
+    }
+
+    /// Replace the transfer options of a watcher by ID, forgetting its snapshots.
+    pub fn update_watcher_options(&self, id: u32, options: &WatcherOptions) -> DbResult<()> {
+        self.ctx
+            .db
+            .get()?
+            .prepare("UPDATE watchers SET options = ?1, source_snapshot = NULL, target_snapshot = NULL WHERE watchers.id = ?2")?
+            .execute(params![options_to_value(options)?, id])?;
 
         Ok(())
     }
@@ -150,4 +281,24 @@ impl WatcherRepo {
 
         Ok(())
     }
+
+    /// Delete watchers whose user no longer exists, returning how many were deleted.
+    pub fn delete_orphaned_watchers(&self) -> DbResult<usize> {
+        Ok(self
+            .ctx
+            .db
+            .get()?
+            .prepare("DELETE FROM watchers WHERE user_uri NOT IN (SELECT user_uri FROM users)")?
+            .execute([])?)
+    }
+}
+
+/// Serialize rules for storage, storing nothing if no rules are set
+fn rules_to_value(rules: &WatcherRules) -> DbResult<Option<String>> {
+    Ok((!rules.is_empty()).then(|| serde_json::to_string(rules)).transpose()?)
+}
+
+/// Serialize options for storage, storing nothing if every option is left at its default
+fn options_to_value(options: &WatcherOptions) -> DbResult<Option<String>> {
+    Ok((!options.is_default()).then(|| serde_json::to_string(options)).transpose()?)
 }
@@ -2,7 +2,11 @@
 
 use clap::Parser as _;
 use error::BaseResult;
-use futures::{future::FutureExt, pin_mut, select};
+use futures::{
+    future::{FutureExt, TryFutureExt},
+    pin_mut, select,
+};
+use std::time::Duration;
 use tracing_subscriber::prelude::*;
 
 mod api;
@@ -11,9 +15,15 @@ mod config;
 mod context;
 mod db;
 mod error;
+mod shutdown;
 mod sync;
+#[cfg(test)]
+mod testing;
 mod web;
 
+/// How long to wait for queued Sentry events to be sent when shutting down
+const SENTRY_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
 fn main() -> BaseResult<()> {
     // Ensure config dir exists
     config::init_config_dir()?;
@@ -22,7 +32,7 @@ fn main() -> BaseResult<()> {
     let config = config::parse(args.config)?;
 
     // Initialize Sentry if we have a DSN
-    let _sentry = sentry::init((
+    let sentry_guard = sentry::init((
         config.sentry.dsn.clone(),
         sentry::ClientOptions {
             release: sentry::release_name!(),
@@ -45,6 +55,24 @@ fn main() -> BaseResult<()> {
             config::init_config_file(force)?;
         }
 
+        args::Command::Migrate { command } => {
+            migrate(config, command)?;
+        }
+
+        args::Command::Sync { dry_run } => {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?
+                .block_on(async { sync_once(config, dry_run).await })?;
+        }
+
+        args::Command::Undo { transfer_id } => {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?
+                .block_on(async { undo(config, transfer_id).await })?;
+        }
+
         args::Command::Start => {
             // Start thread to run web and sync tasks
             if let Err(err) = tokio::runtime::Builder::new_multi_thread()
@@ -55,28 +83,128 @@ fn main() -> BaseResult<()> {
                 tracing::error!("{}", err);
                 sentry::capture_error(&err);
             };
+
+            // Send any queued events before exiting, as the process may be killed soon after
+            sentry_guard.flush(Some(SENTRY_FLUSH_TIMEOUT));
         }
     }
 
     Ok(())
 }
 
-async fn start(config: config::ModulateConfig) -> BaseResult<()> {
+/// Open the database, applying any pending migrations, and build the shared app context
+fn app_context(config: config::ModulateConfig) -> BaseResult<context::AppContext> {
     let db_path = config::get_config_dir()?.join(&config.database.file);
 
     let db = db::init(&db_path)?;
-    let ctx = context::AppContext { db, config };
+    let http = api::http::HttpClients::new(&config.spotify)?;
+
+    Ok(context::AppContext { db, config, http })
+}
+
+fn migrate(config: config::ModulateConfig, command: args::MigrateCommand) -> BaseResult<()> {
+    let db_path = config::get_config_dir()?.join(&config.database.file);
+    let mut conn = db::connect(&db_path)?.get().map_err(db::error::DbError::from)?;
+
+    match command {
+        args::MigrateCommand::Status => {
+            let status = db::migrate::status(&conn)?;
 
-    // Run web server and sync tasks concurrently
-    let web = web::serve(ctx.clone()).fuse();
-    let sync = sync::init(ctx).fuse();
+            tracing::info!(
+                "Database schema is at version {} (latest is {})",
+                status.current,
+                status.latest
+            );
 
-    pin_mut!(web, sync);
+            for migration in status.pending {
+                tracing::info!("Pending: {:04}_{}", migration.version, migration.name);
+            }
+        }
+
+        args::MigrateCommand::Up => {
+            let applied = db::migrate::up(&mut conn)?;
+
+            if applied.is_empty() {
+                tracing::info!("Database schema is already up to date");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn sync_once(config: config::ModulateConfig, dry_run: bool) -> BaseResult<()> {
+    let ctx = app_context(config)?;
+
+    if dry_run {
+        sync::preview(ctx).await?;
+    } else {
+        sync::execute(ctx).await?;
+    }
+
+    Ok(())
+}
+
+async fn undo(config: config::ModulateConfig, transfer_id: u32) -> BaseResult<()> {
+    let ctx = app_context(config)?;
+
+    let transfer = db::repo::transfer::TransferRepo::new(ctx.clone())
+        .get_transfer_by_id(transfer_id)?
+        .ok_or(sync::error::SyncError::TransferNotFound(transfer_id))?;
+    let watcher = db::repo::watcher::WatcherRepo::new(ctx.clone())
+        .get_watcher_by_id(transfer.watcher_id)?
+        .ok_or(sync::error::SyncError::WatcherNotFound(transfer.watcher_id))?;
+    let user = db::repo::user::UserRepo::new(ctx.clone())
+        .find_user_by_uri(&watcher.user_uri)?
+        .ok_or_else(|| sync::error::SyncError::UserNotFound(watcher.user_uri.clone()))?;
+
+    let (client, _) = api::client::Client::from_user_ensure_refreshed(ctx.clone(), user).await?;
+    let count = sync::undo_transfer(ctx, client, &transfer).await?;
+
+    tracing::info!(
+        "Undid transfer {}, restoring {} track(s)",
+        transfer_id,
+        count
+    );
+
+    Ok(())
+}
+
+async fn start(config: config::ModulateConfig) -> BaseResult<()> {
+    let ctx = app_context(config)?;
+    let shutdown_timeout = Duration::from_secs(ctx.config.sync.shutdown_timeout_secs.into());
+    let (request_shutdown, shutdown) = shutdown::Shutdown::new();
+
+    // Run web server and sync tasks concurrently. Both return once shutdown is requested and they
+    // have finished what they were doing, or straight away if either errors.
+    let run = futures::future::try_join(
+        web::serve(ctx.clone(), shutdown.clone()).err_into::<error::BaseError>(),
+        sync::init(ctx, shutdown).err_into::<error::BaseError>(),
+    )
+    .fuse();
+
+    // On SIGINT or SIGTERM, stop accepting requests and starting syncs, then give in-flight work a
+    // limited amount of time to finish
+    let deadline = async {
+        shutdown::signal().await;
+        tracing::info!(
+            "Shutting down, waiting up to {}s for requests and syncs to finish",
+            shutdown_timeout.as_secs()
+        );
+        request_shutdown.send_replace(true);
+
+        tokio::time::sleep(shutdown_timeout).await;
+        tracing::warn!("Shutdown timed out, exiting with work still in progress");
+    }
+    .fuse();
+
+    pin_mut!(run, deadline);
 
-    // Wait for either process to finish (i.e. return an error) and exit
     select! {
-        result = web => result?,
-        result = sync => result?,
+        result = run => {
+            result?;
+        }
+        _ = deadline => {}
     };
 
     Ok(())
@@ -0,0 +1,54 @@
+use tokio::sync::watch;
+
+/// Lets long-running tasks know that the app has been asked to shut down
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Create a handle along with the sender used to request the shutdown
+    pub fn new() -> (watch::Sender<bool>, Self) {
+        let (tx, rx) = watch::channel(false);
+        (tx, Self(rx))
+    }
+
+    /// A handle for one-off commands, where shutdown is never requested
+    pub fn never() -> Self {
+        Self(watch::channel(false).1)
+    }
+
+    /// Whether shutdown has been requested
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until shutdown is requested
+    pub async fn requested(&mut self) {
+        if self.0.wait_for(|requested| *requested).await.is_err() {
+            // The sender is gone without requesting shutdown, so it never will be
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Wait for SIGINT (Ctrl+C) or, on Unix, SIGTERM
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
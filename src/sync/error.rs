@@ -8,6 +8,12 @@ pub enum SyncError {
     #[error("could not find user: {0}")]
     UserNotFound(String),
 
+    #[error("could not find watcher: {0}")]
+    WatcherNotFound(u32),
+
+    #[error("could not find transfer: {0}")]
+    TransferNotFound(u32),
+
     #[error(transparent)]
     DbError(#[from] crate::db::error::DbError),
 
@@ -3,17 +3,77 @@ use crate::{
     api::client::{self, Client, WithToken},
     context::AppContext,
     db::{
-        model::watcher::Watcher,
-        repo::{transfer::TransferRepo, user::UserRepo, watcher::WatcherRepo},
+        model::{journal::Journal, transfer::Transfer, watcher::Watcher},
+        repo::{
+            journal::JournalRepo, transfer::TransferRepo, user::UserRepo, watcher::WatcherRepo,
+        },
     },
+    shutdown::Shutdown,
     sync::error::SyncError,
 };
 use chrono::{DateTime, Timelike, Utc};
+use futures::StreamExt;
+use sentry::SentryFutureExt;
+use std::sync::Arc;
 
 pub mod error;
+pub mod preview;
 pub mod transfer;
+pub mod undo;
 
-pub async fn init(ctx: AppContext) -> SyncResult<()> {
+/// Delay before restarting the sync worker the first time, doubled for each restart in a row
+const RESTART_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Longest delay between restarts of the sync worker
+const RESTART_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Run the sync worker, restarting it with a backoff whenever it errors or panics so that a
+/// failing database can't stop syncing (or the web server alongside it) for good. Returns once shutdown
+/// is requested and any syncs in progress have finished.
+pub async fn init(ctx: AppContext, mut shutdown: Shutdown) -> SyncResult<()> {
+    let mut restarts = 0;
+
+    resume_interrupted_transfers(ctx.clone(), Utc::now()).await;
+
+    loop {
+        let started_at = tokio::time::Instant::now();
+
+        match tokio::spawn(run(ctx.clone(), shutdown.clone())).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(err)) => {
+                tracing::error!("Sync worker stopped: {}", err);
+                sentry::capture_error(&err);
+            }
+            Err(err) => {
+                tracing::error!("Sync worker panicked: {}", err);
+                sentry::capture_error(&err);
+            }
+        }
+
+        // A worker that ran for a while before failing starts backing off from the beginning again
+        if started_at.elapsed() > RESTART_MAX_DELAY {
+            restarts = 0;
+        }
+
+        let delay = restart_delay(restarts);
+        restarts += 1;
+
+        tracing::info!("Restarting sync worker in {:.0}s", delay.as_secs_f64());
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {},
+            _ = shutdown.requested() => return Ok(()),
+        }
+    }
+}
+
+fn restart_delay(restarts: u32) -> std::time::Duration {
+    RESTART_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(restarts))
+        .min(RESTART_MAX_DELAY)
+}
+
+/// Check for due watchers every `sync.check_interval_mins` until shutdown is requested or a check fails
+async fn run(ctx: AppContext, mut shutdown: Shutdown) -> SyncResult<()> {
     loop {
         let now = Utc::now();
         let next_update = now
@@ -36,76 +96,283 @@ pub async fn init(ctx: AppContext) -> SyncResult<()> {
             time_until_next_update.as_secs_f64(),
         );
 
-        tokio::time::sleep_until(
-            tokio::time::Instant::now().checked_add(time_until_next_update).unwrap(),
-        )
-        .await;
+        tokio::select! {
+            _ = tokio::time::sleep(time_until_next_update) => {},
+            _ = shutdown.requested() => return Ok(()),
+        }
+
+        // Individual watchers can't fail a check, so this only errors if the watchers can't be loaded
+        execute_until_shutdown(ctx.clone(), shutdown.clone()).await?;
 
-        // Kill thread if worker task errored
-        execute(ctx.clone()).await?;
+        if shutdown.is_requested() {
+            return Ok(());
+        }
     }
 }
 
-async fn execute(ctx: AppContext) -> SyncResult<()> {
-    let user_repo = UserRepo::new(ctx.clone());
+/// Sync every watcher that is due once.
+///
+/// Users are synced concurrently, up to `sync.max_concurrency` at a time, but each user's watchers are
+/// synced one after another so that two watchers never change the same account's playlists at once.
+pub async fn execute(ctx: AppContext) -> SyncResult<()> {
+    execute_until_shutdown(ctx, Shutdown::never()).await
+}
+
+/// Sync every watcher that is due once, without starting any more syncs after shutdown is requested.
+/// Syncs already in progress are left to finish so a transfer isn't stopped halfway through.
+async fn execute_until_shutdown(ctx: AppContext, shutdown: Shutdown) -> SyncResult<()> {
     let watcher_repo = WatcherRepo::new(ctx.clone());
+
+    // Watchers aren't tied to users in the database, so they can outlive a deleted user
+    let num_orphaned = watcher_repo.delete_orphaned_watchers()?;
+    if num_orphaned > 0 {
+        tracing::warn!(
+            "Deleted {} watcher(s) of users that no longer exist",
+            num_orphaned
+        );
+    }
+
     let watchers = watcher_repo.get_all_watchers()?;
 
-    let to_sync = watchers
-        .into_iter()
-        .filter(|watcher| {
-            watcher.next_sync_at.is_none()
-                || watcher.next_sync_at.is_some_and(|next_sync| next_sync <= Utc::now())
-        })
-        .collect::<Vec<_>>();
+    // Group due watchers by user, keeping the order they were created in
+    let mut by_user = Vec::<(String, Vec<Watcher>)>::new();
+    for watcher in watchers.into_iter().filter(is_due) {
+        match by_user.iter_mut().find(|(user_uri, _)| *user_uri == watcher.user_uri) {
+            Some((_, watchers)) => watchers.push(watcher),
+            None => by_user.push((watcher.user_uri.clone(), vec![watcher])),
+        }
+    }
 
-    if to_sync.is_empty() {
+    if by_user.is_empty() {
         return Ok(());
     }
 
-    tracing::info!("Syncing {} watcher(s)...", to_sync.len());
+    tracing::info!(
+        "Syncing {} watcher(s) for {} user(s)...",
+        by_user.iter().map(|(_, watchers)| watchers.len()).sum::<usize>(),
+        by_user.len()
+    );
 
     let now = Utc::now().with_second(0).unwrap().with_nanosecond(0).unwrap();
+    let max_concurrency = ctx.config.sync.max_concurrency.max(1) as usize;
+
+    futures::stream::iter(by_user)
+        .map(|(user_uri, watchers)| {
+            // Each user gets their own Sentry scope so concurrent syncs don't overwrite each other's user
+            let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+            sync_user_watchers(ctx.clone(), &shutdown, user_uri, watchers, now).bind_hub(hub)
+        })
+        .buffer_unordered(max_concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+    tracing::info!("Synced");
+
+    Ok(())
+}
+
+/// Sync a single user's due watchers one after another. Errors are reported and counted against
+/// the watcher they happened to, so they never stop other watchers from syncing.
+async fn sync_user_watchers(
+    ctx: AppContext,
+    shutdown: &Shutdown,
+    user_uri: String,
+    watchers: Vec<Watcher>,
+    now: DateTime<Utc>,
+) {
+    if shutdown.is_requested() {
+        return;
+    }
+
+    let watcher_repo = WatcherRepo::new(ctx.clone());
+    let user = match UserRepo::new(ctx.clone()).find_user_by_uri(&user_uri) {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            // Deleted since the orphan cleanup ran, so the next check will remove the watchers
+            tracing::warn!("Skipping watchers of missing user {}", user_uri);
+            return;
+        }
+        Err(err) => {
+            tracing::error!("Failed to load user {}: {}", user_uri, err);
+            sentry::capture_error(&err);
+            return;
+        }
+    };
+
+    sentry::configure_scope(|scope| {
+        scope.set_user(Some(sentry::User {
+            id: Some(user.user_uri.clone()),
+            ..Default::default()
+        }));
+    });
+
+    let time_zone = user.time_zone;
+    let (client, _) = match client::Client::from_user_ensure_refreshed(ctx.clone(), user).await {
+        Ok(val) => val,
+        Err(err) => {
+            tracing::error!("Failed to refresh token for {}: {}", user_uri, err);
+            sentry::capture_error(&err);
+            for watcher in &watchers {
+                record_failure(&ctx, &watcher_repo, watcher, &err.to_string());
+            }
+            return;
+        }
+    };
+
+    for watcher in watchers {
+        // Leave the rest of the user's watchers due so they sync after a restart
+        if shutdown.is_requested() {
+            tracing::info!(
+                "Shutting down, skipping watcher {} until next time",
+                watcher.id
+            );
+            continue;
+        }
+
+        let res = sync_and_reschedule(
+            &ctx,
+            client.clone(),
+            &watcher_repo,
+            &watcher,
+            now,
+            time_zone,
+        );
+
+        if let Err(err) = res.await {
+            tracing::error!("Error when syncing watcher {}: {}", watcher.id, err);
+            sentry::capture_error(&err);
+            record_failure(&ctx, &watcher_repo, &watcher, &err.to_string());
+        }
+    }
+}
+
+/// Sync a watcher and schedule its next sync
+async fn sync_and_reschedule(
+    ctx: &AppContext,
+    client: Client<WithToken>,
+    watcher_repo: &WatcherRepo,
+    watcher: &Watcher,
+    now: DateTime<Utc>,
+    time_zone: chrono_tz::Tz,
+) -> SyncResult<()> {
+    sync_watcher(ctx.clone(), client, watcher_repo, watcher, now, time_zone).await?;
+
+    watcher_repo
+        .update_watcher_next_sync_at(watcher.id, watcher.schedule.next_after(now, time_zone))?;
+
+    if watcher.consecutive_failures > 0 {
+        watcher_repo.reset_watcher_failures(watcher.id)?;
+    }
+
+    Ok(())
+}
+
+/// Log what syncing every watcher that is due would do, without changing anything.
+pub async fn preview(ctx: AppContext) -> SyncResult<()> {
+    let user_repo = UserRepo::new(ctx.clone());
+    let to_sync = WatcherRepo::new(ctx.clone())
+        .get_all_watchers()?
+        .into_iter()
+        .filter(is_due)
+        .collect::<Vec<_>>();
+
+    tracing::info!("Previewing {} watcher(s)...", to_sync.len());
 
     for watcher in to_sync {
-        let user = user_repo
-            .find_user_by_uri(&watcher.user_uri)?
-            .ok_or_else(|| SyncError::UserNotFound(watcher.user_uri.clone()))?;
+        // Like a real sync, a watcher whose user can't be found or signed in is reported and skipped
+        let res = async {
+            let user = user_repo
+                .find_user_by_uri(&watcher.user_uri)?
+                .ok_or_else(|| SyncError::UserNotFound(watcher.user_uri.clone()))?;
+            let time_zone = user.time_zone;
+            let (client, _) = client::Client::from_user_ensure_refreshed(ctx.clone(), user).await?;
 
-        sentry::configure_scope(|scope| {
-            scope.set_user(Some(sentry::User {
-                id: Some(user.user_uri.clone()),
-                ..Default::default()
-            }));
-        });
+            preview::preview_watcher(ctx.clone(), client, &watcher, time_zone).await
+        };
 
-        let (client, _) = match client::Client::from_user_ensure_refreshed(ctx.clone(), user).await
-        {
-            Ok(val) => val,
+        let preview = match res.await {
+            Ok(preview) => preview,
             Err(err) => {
-                tracing::error!("Failed to refresh token for {}: {}", watcher.user_uri, err);
-                sentry::capture_error(&err);
+                tracing::error!("Error when previewing watcher {}: {}", watcher.id, err);
                 continue;
             }
         };
 
-        match sync_watcher(ctx.clone(), client, &watcher_repo, &watcher, now).await {
-            Ok(_) => {
-                // Set the next interval
-                watcher_repo.update_watcher_next_sync_at(
-                    watcher.id,
-                    now.checked_add_signed(watcher.sync_interval.clone().into()).unwrap(),
-                )?;
-            }
-            Err(err) => {
-                // Don't kill worker thread if an individual sync task errored
-                tracing::error!("Error when syncing watcher: {}", err);
-                sentry::capture_error(&err);
+        tracing::info!(
+            "Watcher {} ({} -> {}): {} to add, {} duplicate(s) to skip, {} to remove, {} local file(s) to skip, {} episode(s) that can't be liked, {} to evict",
+            watcher.id,
+            watcher.playlist_from,
+            watcher.playlist_to,
+            preview.added.len(),
+            preview.skipped_duplicates.len(),
+            preview.removed.len(),
+            preview.skipped_local.len(),
+            preview.skipped_unsupported.len(),
+            preview.evicted.len(),
+        );
+
+        for (label, tracks) in [
+            ("add", &preview.added),
+            ("skip", &preview.skipped_duplicates),
+            ("remove", &preview.removed),
+            ("local", &preview.skipped_local),
+            ("unsupported", &preview.skipped_unsupported),
+            ("evict", &preview.evicted),
+        ] {
+            for track in tracks {
+                tracing::info!(
+                    "  {} {} - {} ({})",
+                    label,
+                    track.artists.join(", "),
+                    track.name,
+                    track.id.uri()
+                );
             }
         }
     }
 
-    tracing::info!("Synced");
+    Ok(())
+}
+
+/// Whether a watcher is enabled and has never been synced or its next sync is overdue
+fn is_due(watcher: &Watcher) -> bool {
+    watcher.enabled && watcher.next_sync_at.is_none_or(|next_sync| next_sync <= Utc::now())
+}
+
+/// Count a failed sync, pausing the watcher once it has failed too many times in a row so a broken
+/// watcher isn't retried (and reported) forever.
+fn record_failure(ctx: &AppContext, watcher_repo: &WatcherRepo, watcher: &Watcher, error: &str) {
+    if let Err(err) = try_record_failure(ctx, watcher_repo, watcher, error) {
+        tracing::error!(
+            "Failed to record failure of watcher {}: {}",
+            watcher.id,
+            err
+        );
+        sentry::capture_error(&err);
+    }
+}
+
+fn try_record_failure(
+    ctx: &AppContext,
+    watcher_repo: &WatcherRepo,
+    watcher: &Watcher,
+    error: &str,
+) -> SyncResult<()> {
+    let failures = watcher_repo.increment_watcher_failures(watcher.id)?;
+    let max_failures = ctx.config.sync.max_consecutive_failures;
+
+    if max_failures > 0 && failures >= max_failures {
+        tracing::warn!(
+            "Pausing watcher {} after {} failed syncs in a row",
+            watcher.id,
+            failures
+        );
+        watcher_repo.update_watcher_enabled(
+            watcher.id,
+            false,
+            Some(&format!("Failed {} times in a row: {}", failures, error)),
+        )?;
+    }
 
     Ok(())
 }
@@ -117,22 +384,134 @@ pub async fn sync_watcher(
     watcher_repo: &WatcherRepo,
     watcher: &Watcher,
     now: DateTime<Utc>,
+    time_zone: chrono_tz::Tz,
 ) -> SyncResult<u32> {
-    let res = sync_watcher_inner(ctx.clone(), client, watcher_repo, watcher, &now).await;
-
-    let num_tracks = *res.as_ref().unwrap_or(&0);
+    let mut transfer = transfer::PlaylistTransfer::new(ctx.clone(), client);
+    let res = sync_watcher_inner(&mut transfer, watcher_repo, watcher, &now, time_zone).await;
+    let journal_id = transfer.journal_id();
+    let tracks = transfer.into_track_log();
 
-    // Only log if we've actually transferred tracks
-    if num_tracks == 0 {
+    // Only log if we've actually changed tracks or something went wrong. Local files and episodes bound
+    // for Liked Tracks are skipped on every sync, so on their own they would fill the log with identical entries.
+    if matches!(res, Ok(0)) && tracks.iter().all(|track| track.action.is_untransferable()) {
         return Ok(0);
     }
 
-    // Save transfer result
-    TransferRepo::new(ctx.clone()).log_transfer(
+    // Save transfer result, closing its journal now that it's finished
+    let transfer_repo = TransferRepo::new(ctx.clone());
+    let num_transferred = res.as_ref().unwrap_or(&0);
+    match journal_id {
+        Some(journal_id) => transfer_repo.log_journaled_transfer(
+            journal_id,
+            watcher.id,
+            num_transferred,
+            &res.as_ref().err(),
+            now,
+            &tracks,
+        )?,
+        None => transfer_repo.log_transfer(
+            watcher.id,
+            num_transferred,
+            &res.as_ref().err(),
+            now,
+            &tracks,
+            None,
+        )?,
+    };
+
+    res
+}
+
+/// Finish any transfers that were interrupted by a crash or a forced shutdown before they could be
+/// logged. Only journals created before `before` are resumed, so transfers running right now are left alone.
+pub async fn resume_interrupted_transfers(ctx: AppContext, before: DateTime<Utc>) {
+    let journals = match JournalRepo::new(ctx.clone()).get_unfinished_journals(before) {
+        Ok(journals) => journals,
+        Err(err) => {
+            tracing::error!("Failed to load interrupted transfers: {}", err);
+            sentry::capture_error(&err);
+            return;
+        }
+    };
+
+    for journal in journals {
+        if let Err(err) = resume_transfer(ctx.clone(), &journal).await {
+            tracing::error!(
+                "Error when resuming transfer of watcher {}: {}",
+                journal.watcher_id,
+                err
+            );
+            sentry::capture_error(&err);
+        }
+    }
+}
+
+/// Resume an interrupted transfer and log it, or discard it if it can no longer be resumed. A
+/// discarded transfer is still safe, as the watcher's next sync plans from the playlists as they are.
+async fn resume_transfer(ctx: AppContext, journal: &Journal) -> SyncResult<()> {
+    let journal_repo = JournalRepo::new(ctx.clone());
+    let watcher = WatcherRepo::new(ctx.clone()).get_watcher_by_id(journal.watcher_id)?;
+    let user = match &watcher {
+        Some(watcher) => UserRepo::new(ctx.clone()).find_user_by_uri(&watcher.user_uri)?,
+        None => None,
+    };
+
+    let (Some(watcher), Some(user)) = (watcher, user) else {
+        tracing::warn!(
+            "Discarding interrupted transfer of deleted watcher {}",
+            journal.watcher_id
+        );
+        return Ok(journal_repo.delete_journal(journal.id)?);
+    };
+
+    let client = match client::Client::from_user_ensure_refreshed(ctx.clone(), user).await {
+        Ok((client, _)) => client,
+        Err(err) => {
+            journal_repo.delete_journal(journal.id)?;
+            return Err(err.into());
+        }
+    };
+
+    tracing::info!("Resuming interrupted transfer of watcher {}", watcher.id);
+
+    let mut transfer = transfer::PlaylistTransfer::new(ctx.clone(), client);
+    let res = transfer.resume(&watcher, journal).await;
+    let tracks = transfer.into_track_log();
+
+    TransferRepo::new(ctx).log_journaled_transfer(
+        journal.id,
         watcher.id,
         res.as_ref().unwrap_or(&0),
         &res.as_ref().err(),
-        now,
+        journal.synced_at,
+        &tracks,
+    )?;
+
+    res.map(|_| ())
+}
+
+/// Undo a transfer and save the result as a new transfer for the same watcher.
+pub async fn undo_transfer(
+    ctx: AppContext,
+    client: Client<WithToken>,
+    transfer: &Transfer,
+) -> SyncResult<u32> {
+    let mut undo = undo::TransferUndo::new(ctx.clone(), client);
+    let res = undo.try_undo(transfer).await;
+    let tracks = undo.into_track_log();
+
+    // Nothing was attempted if the transfer could not be undone
+    if let Err(SyncError::InvalidTransfer(_)) = res {
+        return res;
+    }
+
+    TransferRepo::new(ctx).log_transfer(
+        transfer.watcher_id,
+        res.as_ref().unwrap_or(&0),
+        &res.as_ref().err(),
+        Utc::now(),
+        &tracks,
+        Some(transfer.id),
     )?;
 
     res
@@ -140,16 +519,1466 @@ pub async fn sync_watcher(
 
 /// Sync a watcher and update the `last_sync_at` date
 async fn sync_watcher_inner(
-    ctx: AppContext,
-    client: Client<WithToken>,
+    transfer: &mut transfer::PlaylistTransfer,
     watcher_repo: &WatcherRepo,
     watcher: &Watcher,
     now: &DateTime<Utc>,
+    time_zone: chrono_tz::Tz,
 ) -> SyncResult<u32> {
-    let num_tracks_transferred =
-        transfer::PlaylistTransfer::new(ctx, client).try_transfer(watcher).await?;
+    let num_tracks_transferred = transfer.try_transfer(watcher, *now, time_zone).await?;
 
+    if let Some(snapshots) = transfer.snapshots() {
+        watcher_repo.update_watcher_snapshots(watcher.id, snapshots)?;
+    }
+    let periods = transfer.period_playlists(watcher);
+    if periods != watcher.period_playlists {
+        watcher_repo.update_watcher_period_playlists(watcher.id, &periods)?;
+    }
     watcher_repo.update_watcher_last_sync_at(watcher.id, *now)?;
 
     Ok(num_tracks_transferred)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        api::id::{ItemId, PlaylistId},
+        db::model::{
+            options::{ConflictPolicy, EvictionPolicy, InsertOrder, WatcherOptions},
+            playlist::PlaylistType,
+            rules::WatcherRules,
+            template::PlaylistTemplate,
+            transfer_track::TrackAction,
+            watcher::NewWatcher,
+        },
+        testing::{
+            TEST_USER_ID, TestApp,
+            spotify::{Failure, FakeSpotify, FakeTrack},
+            valid_token,
+        },
+    };
+    use reqwest::{Method, StatusCode};
+    use std::time::Duration;
+
+    const SOURCE: &str = "SourcePlaylist00000000";
+    const TARGET: &str = "TargetPlaylist00000000";
+    const OTHER_TARGET: &str = "OtherTargetPlaylist000";
+
+    fn playlist(id: &str) -> PlaylistType {
+        PlaylistType::Id(PlaylistId(id.to_owned()))
+    }
+
+    /// Builds a watcher for the test user that keeps its defaults unless told otherwise
+    struct TestWatcher {
+        watcher: NewWatcher,
+        user_uri: Option<String>,
+    }
+
+    impl TestWatcher {
+        fn new(from: PlaylistType, to: PlaylistType) -> Self {
+            Self {
+                watcher: NewWatcher::new("", from, to),
+                user_uri: None,
+            }
+        }
+
+        fn user(mut self, user_uri: &str) -> Self {
+            self.user_uri = Some(user_uri.to_owned());
+            self
+        }
+
+        fn should_remove(mut self, should_remove: bool) -> Self {
+            self.watcher.should_remove = should_remove;
+            self
+        }
+
+        fn rules(mut self, rules: WatcherRules) -> Self {
+            self.watcher.rules = rules;
+            self
+        }
+
+        fn options(mut self, options: WatcherOptions) -> Self {
+            self.watcher.options = options;
+            self
+        }
+
+        fn mirror(self, conflict_policy: ConflictPolicy) -> Self {
+            self.options(WatcherOptions {
+                mirror: true,
+                conflict_policy,
+                ..Default::default()
+            })
+        }
+
+        fn extra_sources(mut self, extra_sources: Vec<PlaylistType>) -> Self {
+            self.watcher.extra_sources = extra_sources;
+            self
+        }
+
+        fn extra_targets(mut self, extra_targets: Vec<PlaylistType>) -> Self {
+            self.watcher.extra_targets = extra_targets;
+            self
+        }
+
+        fn create(self, app: &TestApp) {
+            let user_uri = self.user_uri.unwrap_or_else(|| app.user.user_uri.clone());
+            WatcherRepo::new(app.ctx.clone())
+                .create_watcher(&NewWatcher {
+                    user_uri,
+                    ..self.watcher
+                })
+                .unwrap();
+        }
+    }
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    fn track_ids(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("track{:03}", i)).collect()
+    }
+
+    fn set_saved_tracks(spotify: &FakeSpotify, ids: &[String]) {
+        spotify.set_saved_tracks(&ids.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn it_moves_liked_tracks_into_a_playlist() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            sorted(app.spotify.playlist_track_ids(TARGET)),
+            vec!["a", "b", "c"]
+        );
+        assert!(app.spotify.saved_track_ids().is_empty());
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        assert!(watcher.last_sync_at.is_some());
+        assert!(watcher.next_sync_at.is_some_and(|next| next > Utc::now()));
+    }
+
+    #[tokio::test]
+    async fn it_copies_tracks_without_adding_duplicates() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["b"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            sorted(app.spotify.playlist_track_ids(TARGET)),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b", "c"]);
+        assert_eq!(
+            app.spotify
+                .request_count(&Method::DELETE, &format!("/playlists/{}/tracks", SOURCE)),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn it_records_each_track_change() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x", "b"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        let changes = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .map(|track| (track.item_id.id().to_string(), track.action, track.position))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                ("a".to_string(), TrackAction::Added, Some(2)),
+                ("c".to_string(), TrackAction::Added, Some(3)),
+                ("b".to_string(), TrackAction::SkippedDuplicate, Some(1)),
+                ("a".to_string(), TrackAction::Removed, Some(0)),
+                ("b".to_string(), TrackAction::Removed, Some(1)),
+                ("c".to_string(), TrackAction::Removed, Some(2)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_logs_transfers_that_only_remove_tracks() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["a"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let transfers = TransferRepo::new(app.ctx.clone())
+            .get_transfers_for_watcher(watcher.id, 10, 0)
+            .unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].num_tracks_transferred, 0);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_does_not_change_anything_in_a_dry_run() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        preview(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.saved_track_ids(), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(TARGET).is_empty());
+        assert_eq!(app.spotify.request_count(&Method::GET, "/tracks"), 1);
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        assert!(watcher.last_sync_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn it_previews_other_watchers_when_a_user_cannot_be_found() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .user("spotify:user:someone_else")
+            .create(&app);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        preview(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.request_count(&Method::GET, "/tracks"), 1);
+    }
+
+    #[tokio::test]
+    async fn it_only_transfers_tracks_matching_the_rules() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c", "d"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.set_track("b", serde_json::json!({ "explicit": true }));
+        app.spotify.set_track("c", serde_json::json!({ "duration_ms": 30_000 }));
+        app.spotify.state().playlists.get_mut(SOURCE).unwrap().tracks[3].added_at =
+            Utc::now() - chrono::Duration::days(30);
+
+        let rules = WatcherRules {
+            added_within_days: Some(7),
+            exclude_explicit: true,
+            min_duration_secs: Some(60),
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .rules(rules)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn it_skips_watchers_that_are_not_due() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+        app.spotify.set_saved_tracks(&["b", "a"]);
+        execute(app.ctx.clone()).await.unwrap();
+
+        // One page of tracks, plus a summary before the transfer
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 2);
+    }
+
+    #[tokio::test]
+    async fn it_pages_through_large_libraries() {
+        let app = TestApp::start().await;
+        let ids = track_ids(120);
+        set_saved_tracks(&app.spotify, &ids);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(sorted(app.spotify.playlist_track_ids(TARGET)), ids);
+        assert!(app.spotify.saved_track_ids().is_empty());
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 4);
+        assert_eq!(
+            app.spotify
+                .request_count(&Method::POST, &format!("/playlists/{}/tracks", TARGET)),
+            2
+        );
+        assert_eq!(app.spotify.request_count(&Method::DELETE, "/me/tracks"), 3);
+    }
+
+    #[tokio::test]
+    async fn it_leaves_playlists_untouched_when_reading_the_source_fails() {
+        let app = TestApp::start().await;
+        set_saved_tracks(&app.spotify, &track_ids(60));
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::GET,
+            "/me/tracks",
+            Failure::Status(StatusCode::BAD_GATEWAY),
+            10,
+        );
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert!(app.spotify.playlist_track_ids(TARGET).is_empty());
+        assert_eq!(app.spotify.saved_track_ids().len(), 60);
+
+        // The failure is recorded and the watcher will be retried on the next check
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let transfers = TransferRepo::new(app.ctx.clone())
+            .get_transfers_for_watcher(watcher.id, 10, 0)
+            .unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert!(transfers[0].error.is_some());
+        assert!(watcher.next_sync_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn it_does_not_remove_tracks_when_adding_them_fails() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::POST,
+            &format!("/playlists/{}/tracks", TARGET),
+            Failure::TooManyRequests { retry_after: None },
+            10,
+        );
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert!(app.spotify.playlist_track_ids(TARGET).is_empty());
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_add_anything_when_spotify_returns_an_empty_body() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::GET,
+            &format!("/playlists/{}/tracks", SOURCE),
+            Failure::EmptyBody(StatusCode::OK),
+            1,
+        );
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert!(app.spotify.playlist_track_ids(TARGET).is_empty());
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn it_keeps_syncing_other_watchers_when_one_fails() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        // The first watcher's source playlist does not exist
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn it_syncs_users_concurrently_but_each_users_watchers_in_turn() {
+        let delay = Duration::from_millis(300);
+        let app = TestApp::start().await;
+        let other_user = UserRepo::new(app.ctx.clone())
+            .upsert_user_token("spotify:user:other", &valid_token())
+            .unwrap();
+
+        // The test user has two watchers and the other user has one, each reading a slow playlist
+        let user_uris = [&app.user.user_uri, &app.user.user_uri, &other_user.user_uri];
+        for (i, user_uri) in user_uris.into_iter().enumerate() {
+            let (source, target) = (format!("Source{:016}", i), format!("Target{:016}", i));
+            app.spotify.add_playlist(&source, TEST_USER_ID, &["a"]);
+            app.spotify.add_playlist(&target, TEST_USER_ID, &[]);
+            app.spotify.fail(
+                Method::GET,
+                &format!("/playlists/{}/tracks", source),
+                Failure::Delay(delay),
+                1,
+            );
+            TestWatcher::new(playlist(&source), playlist(&target))
+                .user(user_uri)
+                .create(&app);
+        }
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let slow_read = |i: usize| {
+            let mut requests = app
+                .spotify
+                .requests(&Method::GET, &format!("/playlists/Source{:016}/tracks", i));
+            let request = requests.remove(0);
+            (request.received_at, request.responded_at.unwrap())
+        };
+        let (first, second, other) = (slow_read(0), slow_read(1), slow_read(2));
+
+        // The test user's watchers run back to back while the other user's runs alongside them
+        assert!(second.0 >= first.1);
+        assert!(other.0 < second.1 && other.1 > first.0);
+
+        for i in 0..3 {
+            assert_eq!(
+                app.spotify.playlist_track_ids(&format!("Target{:016}", i)),
+                vec!["a"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_deletes_watchers_of_missing_users_without_stopping() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let repo = WatcherRepo::new(app.ctx.clone());
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET))
+            .user("spotify:user:deleted")
+            .create(&app);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let watchers = repo.get_all_watchers().unwrap();
+        assert_eq!(watchers.len(), 1);
+        assert_eq!(watchers[0].user_uri, app.user.user_uri);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+    }
+
+    #[test]
+    fn it_backs_off_between_worker_restarts() {
+        assert_eq!(restart_delay(0), RESTART_BASE_DELAY);
+        assert_eq!(restart_delay(2), RESTART_BASE_DELAY * 4);
+        assert_eq!(restart_delay(100), RESTART_MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn it_finishes_syncs_in_progress_but_starts_no_more_after_shutdown() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::GET,
+            &format!("/playlists/{}/tracks", SOURCE),
+            Failure::Delay(Duration::from_millis(300)),
+            1,
+        );
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        let (request_shutdown, shutdown) = Shutdown::new();
+        let sync = tokio::spawn(execute_until_shutdown(app.ctx.clone(), shutdown.clone()));
+
+        // Ask to shut down while the first watcher is reading its source
+        app.spotify
+            .wait_for_request(&Method::GET, &format!("/playlists/{}/tracks", SOURCE))
+            .await;
+        request_shutdown.send_replace(true);
+        sync.await.unwrap().unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["b"]);
+
+        let watchers = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap();
+        assert!(watchers[0].next_sync_at.is_some());
+        assert!(watchers[1].next_sync_at.is_none());
+
+        // The worker itself stops without waiting for the next check
+        let worker = tokio::time::timeout(Duration::from_secs(10), init(app.ctx.clone(), shutdown));
+        assert!(worker.await.unwrap().is_ok());
+    }
+
+    /// Start syncing and abort it while a request is in flight, as if the process was killed
+    async fn interrupt_sync(app: &TestApp, method: Method, path: &str) {
+        app.spotify.fail(
+            method.clone(),
+            path,
+            Failure::Delay(Duration::from_secs(60)),
+            1,
+        );
+
+        let sync = tokio::spawn(execute(app.ctx.clone()));
+        app.spotify.wait_for_request(&method, path).await;
+        sync.abort();
+        let _ = sync.await;
+    }
+
+    fn assert_logged_once(app: &TestApp, num_tracks_transferred: u32) {
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let transfers = TransferRepo::new(app.ctx.clone())
+            .get_transfers_for_watcher(watcher.id, 10, 0)
+            .unwrap();
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].num_tracks_transferred, num_tracks_transferred);
+        assert_eq!(transfers[0].error, None);
+        assert!(
+            JournalRepo::new(app.ctx.clone())
+                .get_unfinished_journals(Utc::now())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_only_finds_journals_created_before_a_time() {
+        let app = TestApp::start().await;
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+
+        let repo = JournalRepo::new(app.ctx.clone());
+        let before = Utc::now();
+        repo.create_journal(watcher.id, before, &[], &[]).unwrap();
+
+        assert!(repo.get_unfinished_journals(before).unwrap().is_empty());
+        assert_eq!(
+            repo.get_unfinished_journals(Utc::now() + chrono::Duration::seconds(1))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn it_resumes_transfers_interrupted_between_adding_and_removing() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        interrupt_sync(
+            &app,
+            Method::DELETE,
+            &format!("/playlists/{}/tracks", SOURCE),
+        )
+        .await;
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b"]);
+
+        resume_interrupted_transfers(app.ctx.clone(), Utc::now()).await;
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+        assert_logged_once(&app, 2);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_add_tracks_twice_when_resuming() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        interrupt_sync(&app, Method::POST, &format!("/playlists/{}/tracks", TARGET)).await;
+
+        // Spotify got as far as adding one of the tracks before the process died
+        app.spotify
+            .state()
+            .playlists
+            .get_mut(TARGET)
+            .unwrap()
+            .tracks
+            .push(FakeTrack::new("a"));
+
+        resume_interrupted_transfers(app.ctx.clone(), Utc::now()).await;
+
+        // Only the track the resumed transfer added itself is counted
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+        assert_logged_once(&app, 1);
+    }
+
+    #[tokio::test]
+    async fn it_still_adds_planned_duplicates_when_resuming() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let options = WatcherOptions {
+            keep_duplicates: true,
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+
+        interrupt_sync(&app, Method::POST, &format!("/playlists/{}/tracks", TARGET)).await;
+        app.spotify.edit_playlist(TARGET, |tracks| tracks.push(FakeTrack::new("a")));
+
+        resume_interrupted_transfers(app.ctx.clone(), Utc::now()).await;
+
+        // One occurrence was already there, so only the second one is still missing
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b", "a"]);
+        assert_logged_once(&app, 2);
+    }
+
+    const EPISODE: &str = "spotify:episode:ep1";
+    const LOCAL: &str = "spotify:local:Some+Artist:Some+Album:Some+Song:180";
+
+    #[tokio::test]
+    async fn it_transfers_episodes_and_skips_local_files() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", EPISODE, LOCAL]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", EPISODE]);
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec![LOCAL]);
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        assert_eq!(transfer.num_tracks_transferred, 2);
+        assert_eq!(transfer.num_items_skipped, 1);
+
+        let skipped = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .filter(|track| track.action == TrackAction::SkippedLocal)
+            .map(|track| (track.item_id, track.position))
+            .collect::<Vec<_>>();
+        assert_eq!(skipped, vec![(ItemId::Local(LOCAL.to_string()), Some(2))]);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_log_syncs_that_only_skip_local_files() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[LOCAL]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        assert!(
+            TransferRepo::new(app.ctx.clone())
+                .get_transfers_for_watcher(watcher.id, 10, 0)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec![LOCAL]);
+    }
+
+    #[tokio::test]
+    async fn it_skips_paused_watchers() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        let repo = WatcherRepo::new(app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+        repo.update_watcher_enabled(id, false, None).unwrap();
+
+        execute(app.ctx.clone()).await.unwrap();
+        assert!(app.spotify.playlist_track_ids(TARGET).is_empty());
+
+        repo.update_watcher_enabled(id, true, None).unwrap();
+
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn it_pauses_watchers_after_repeated_failures() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        // The source playlist does not exist, so every sync fails
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        let repo = WatcherRepo::new(app.ctx.clone());
+        let max_failures = app.ctx.config.sync.max_consecutive_failures;
+
+        for _ in 1..max_failures {
+            execute(app.ctx.clone()).await.unwrap();
+        }
+
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert!(watcher.enabled);
+        assert_eq!(watcher.consecutive_failures, max_failures - 1);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert!(!watcher.enabled);
+        assert!(
+            watcher
+                .paused_reason
+                .is_some_and(|reason| reason.starts_with(&format!("Failed {} times", max_failures)))
+        );
+
+        // Paused watchers aren't retried, and resuming gives them a fresh start
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(
+            repo.get_all_watchers().unwrap()[0].consecutive_failures,
+            max_failures
+        );
+
+        repo.update_watcher_enabled(watcher.id, true, None).unwrap();
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert!(watcher.enabled);
+        assert_eq!(watcher.consecutive_failures, 0);
+        assert!(watcher.paused_reason.is_none());
+    }
+
+    #[tokio::test]
+    async fn it_resets_the_failure_count_after_a_successful_sync() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        let repo = WatcherRepo::new(app.ctx.clone());
+
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(repo.get_all_watchers().unwrap()[0].consecutive_failures, 1);
+
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(repo.get_all_watchers().unwrap()[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn it_appends_tracks_in_the_order_they_were_added_to_the_source() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x"]);
+        app.spotify.set_saved_tracks(&["d", "e"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        // "a" was added last, then moved to the top of the source
+        app.spotify.state().playlists.get_mut(SOURCE).unwrap().tracks[0].added_at = Utc::now();
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            app.spotify.playlist_track_ids(TARGET),
+            vec!["x", "b", "c", "a", "e", "d"]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_prepends_tracks_newest_first() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x"]);
+        let options = WatcherOptions {
+            insert_order: InsertOrder::Prepend,
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            app.spotify.playlist_track_ids(TARGET),
+            vec!["c", "b", "a", "x"]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_sorts_tracks_by_a_field_before_appending_them() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x"]);
+        app.spotify
+            .set_track("a", serde_json::json!({ "artists": [{ "name": "Zed" }] }));
+        app.spotify
+            .set_track("c", serde_json::json!({ "artists": [{ "name": "alpha" }] }));
+        let options = WatcherOptions {
+            insert_order: InsertOrder::SortByArtist,
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            app.spotify.playlist_track_ids(TARGET),
+            vec!["x", "c", "b", "a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_keeps_duplicate_occurrences_only_when_asked() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "a", "a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(OTHER_TARGET, TEST_USER_ID, &[]);
+        let options = WatcherOptions {
+            keep_duplicates: true,
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+        TestWatcher::new(playlist(SOURCE), playlist(OTHER_TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        // The occurrence already in the target counts towards the three in the source
+        assert_eq!(
+            app.spotify.playlist_track_ids(TARGET),
+            vec!["a", "b", "a", "a"]
+        );
+        assert_eq!(app.spotify.playlist_track_ids(OTHER_TARGET), vec!["a", "b"]);
+    }
+
+    /// Make every watcher due and run the worker again
+    async fn sync_again(app: &TestApp) {
+        let repo = WatcherRepo::new(app.ctx.clone());
+        for watcher in repo.get_all_watchers().unwrap() {
+            repo.update_watcher_next_sync_at(watcher.id, Utc::now()).unwrap();
+        }
+
+        execute(app.ctx.clone()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_skips_reading_playlists_that_have_not_changed() {
+        let app = TestApp::start().await;
+        let tracks_path = format!("/playlists/{}/tracks", SOURCE);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+        sync_again(&app).await;
+        assert_eq!(app.spotify.request_count(&Method::GET, &tracks_path), 1);
+
+        // Adding to the source moves its snapshot on
+        {
+            let mut state = app.spotify.state();
+            let source = state.playlists.get_mut(SOURCE).unwrap();
+            source.tracks.push(FakeTrack::new("b"));
+            source.snapshot += 1;
+        }
+
+        sync_again(&app).await;
+        assert_eq!(app.spotify.request_count(&Method::GET, &tracks_path), 2);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn it_notices_changes_to_liked_tracks_without_reading_them() {
+        let app = TestApp::start().await;
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 2);
+
+        sync_again(&app).await;
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 3);
+
+        app.spotify.state().saved_tracks.insert(0, FakeTrack::new("b"));
+        sync_again(&app).await;
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/tracks"), 5);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn it_transfers_tracks_added_to_the_source_during_a_sync() {
+        let app = TestApp::start().await;
+        let add_path = format!("/playlists/{}/tracks", TARGET);
+        app.spotify.set_saved_tracks(&["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::POST,
+            &add_path,
+            Failure::Delay(Duration::from_millis(500)),
+            1,
+        );
+        TestWatcher::new(PlaylistType::Saved, playlist(TARGET)).create(&app);
+
+        let sync = tokio::spawn(execute(app.ctx.clone()));
+        app.spotify.wait_for_request(&Method::POST, &add_path).await;
+        app.spotify.state().saved_tracks.insert(0, FakeTrack::new("b"));
+        sync.await.unwrap().unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a"]);
+
+        // The track liked while the first sync was running is only picked up by the next one
+        sync_again(&app).await;
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn it_reads_playlists_again_after_a_watcher_is_edited() {
+        let app = TestApp::start().await;
+        let tracks_path = format!("/playlists/{}/tracks", SOURCE);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET)).create(&app);
+        let repo = WatcherRepo::new(app.ctx.clone());
+
+        execute(app.ctx.clone()).await.unwrap();
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert!(watcher.snapshots.is_some());
+
+        repo.update_watcher_options(watcher.id, &WatcherOptions::default()).unwrap();
+        assert!(repo.get_all_watchers().unwrap()[0].snapshots.is_none());
+
+        sync_again(&app).await;
+        assert_eq!(app.spotify.request_count(&Method::GET, &tracks_path), 2);
+    }
+
+    #[tokio::test]
+    async fn it_merges_several_sources_without_duplicates() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.set_saved_tracks(&["c", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .extra_sources(vec![PlaylistType::Saved])
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(
+            sorted(app.spotify.playlist_track_ids(TARGET)),
+            vec!["a", "b", "c"]
+        );
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+        assert!(app.spotify.saved_track_ids().is_empty());
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        assert_eq!(transfer.num_tracks_transferred, 3);
+
+        // "b" is removed from both sources, but only added once
+        let removed_from = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .filter(|track| track.action == TrackAction::Removed && track.item_id.id() == "b")
+            .map(|track| track.playlist_from)
+            .collect::<Vec<_>>();
+        assert_eq!(removed_from, vec![playlist(SOURCE), PlaylistType::Saved]);
+    }
+
+    #[tokio::test]
+    async fn it_copies_a_source_to_several_targets() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(OTHER_TARGET, TEST_USER_ID, &[]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .extra_targets(vec![playlist(OTHER_TARGET)])
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert_eq!(app.spotify.playlist_track_ids(OTHER_TARGET), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_only_removes_from_sources_once_every_target_has_its_tracks() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.add_playlist(OTHER_TARGET, TEST_USER_ID, &[]);
+        app.spotify.fail(
+            Method::POST,
+            &format!("/playlists/{}/tracks", OTHER_TARGET),
+            Failure::Status(StatusCode::FORBIDDEN),
+            1,
+        );
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .extra_targets(vec![playlist(OTHER_TARGET)])
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(OTHER_TARGET).is_empty());
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b"]);
+
+        // The next sync finishes the job without adding to the first target again
+        sync_again(&app).await;
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["a", "b"]);
+        assert_eq!(app.spotify.playlist_track_ids(OTHER_TARGET), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_mirrors_tracks_from_both_playlists_on_the_first_sync() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["b", "c"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .mirror(ConflictPolicy::Keep)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b", "c"]);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    async fn it_makes_a_mirror_match_the_preferred_playlist_on_the_first_sync() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["b", "c"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .mirror(ConflictPolicy::PreferSource)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["a", "b"]);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn it_mirrors_additions_and_removals_in_both_directions() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["a", "b", "c"]);
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .mirror(ConflictPolicy::Keep)
+            .create(&app);
+        execute(app.ctx.clone()).await.unwrap();
+
+        app.spotify.edit_playlist(SOURCE, |tracks| {
+            tracks.retain(|track| track.id != "a");
+            tracks.push(FakeTrack::new("d"));
+        });
+        app.spotify.edit_playlist(TARGET, |tracks| {
+            tracks.retain(|track| track.id != "b");
+            tracks.push(FakeTrack::new("e"));
+        });
+        sync_again(&app).await;
+
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["c", "d", "e"]);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["c", "e", "d"]);
+
+        // Nothing changes once both playlists match
+        app.spotify.edit_playlist(TARGET, |_| {});
+        sync_again(&app).await;
+
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["c", "d", "e"]);
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["c", "e", "d"]);
+    }
+
+    #[tokio::test]
+    async fn it_settles_mirror_conflicts_with_the_conflict_policy() {
+        for (policy, expected) in [
+            (ConflictPolicy::Keep, vec!["b", "a"]),
+            (ConflictPolicy::Remove, vec!["b"]),
+        ] {
+            let app = TestApp::start().await;
+            app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+            app.spotify.add_playlist(TARGET, TEST_USER_ID, &["a", "b"]);
+            TestWatcher::new(playlist(SOURCE), playlist(TARGET)).mirror(policy).create(&app);
+            execute(app.ctx.clone()).await.unwrap();
+
+            // Removed from the source, but removed and added again to the target since the last sync
+            app.spotify
+                .edit_playlist(SOURCE, |tracks| tracks.retain(|track| track.id != "a"));
+            app.spotify.edit_playlist(TARGET, |tracks| {
+                tracks.retain(|track| track.id != "a");
+                tracks.push(FakeTrack {
+                    id: "a".to_owned(),
+                    added_at: Utc::now() + chrono::Duration::seconds(1),
+                });
+            });
+            sync_again(&app).await;
+
+            assert_eq!(app.spotify.playlist_track_ids(SOURCE), expected);
+            assert_eq!(app.spotify.playlist_track_ids(TARGET), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_saves_new_tracks_to_liked_tracks_in_batches() {
+        let app = TestApp::start().await;
+        let ids = (0..120).map(|index| format!("t{:03}", index)).collect::<Vec<_>>();
+        app.spotify.add_playlist(
+            SOURCE,
+            TEST_USER_ID,
+            &ids.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        app.spotify.set_saved_tracks(&["t001"]);
+        TestWatcher::new(playlist(SOURCE), PlaylistType::Saved)
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        // Saved oldest first, so the newest track in the source is at the top of Liked Tracks
+        let saved = app.spotify.saved_track_ids();
+        assert_eq!(saved.len(), 120);
+        assert_eq!(saved[0], "t119");
+        assert_eq!(saved[118], "t000");
+        assert_eq!(saved[119], "t001");
+        assert_eq!(app.spotify.request_count(&Method::PUT, "/me/tracks"), 3);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let transfer = TransferRepo::new(app.ctx.clone())
+            .get_transfers_for_watcher(watcher.id, 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(transfer.num_tracks_transferred, 119);
+    }
+
+    #[tokio::test]
+    async fn it_leaves_episodes_that_cannot_be_liked_in_the_source() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", EPISODE]);
+        TestWatcher::new(playlist(SOURCE), PlaylistType::Saved)
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.saved_track_ids(), vec!["a"]);
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec![EPISODE]);
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        assert_eq!(transfer.num_items_skipped, 1);
+
+        let actions = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .map(|track| (track.item_id.id().to_owned(), track.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                ("a".to_owned(), TrackAction::Added),
+                ("ep1".to_owned(), TrackAction::SkippedUnsupported),
+                ("a".to_owned(), TrackAction::Removed),
+            ]
+        );
+    }
+
+    fn template(value: &str) -> PlaylistTemplate {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_moves_tracks_into_the_playlist_for_the_current_period() {
+        let app = TestApp::start().await;
+        let template = template("Moved {year}-{month}");
+        let name = template.render(Utc::now(), app.user.time_zone);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        TestWatcher::new(playlist(SOURCE), PlaylistType::Template(template))
+            .should_remove(true)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        let created = app.spotify.playlist_named(&name).unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(&created), vec!["a", "b"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+
+        // The next sync in the same period finds the playlist instead of creating another
+        app.spotify.edit_playlist(SOURCE, |tracks| tracks.push(FakeTrack::new("c")));
+        sync_again(&app).await;
+
+        assert_eq!(
+            app.spotify.playlist_track_ids(&created),
+            vec!["a", "b", "c"]
+        );
+        let create_path = format!("/users/{}/playlists", TEST_USER_ID);
+        assert_eq!(app.spotify.request_count(&Method::POST, &create_path), 1);
+
+        // The log names the playlist the tracks went to, so the transfer can be undone later
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        let added = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .find(|track| track.action == TrackAction::Added)
+            .unwrap();
+        assert_eq!(added.playlist_to, playlist(&created));
+    }
+
+    #[tokio::test]
+    async fn it_only_copies_tracks_added_during_the_current_period() {
+        let app = TestApp::start().await;
+        let template = template("Added in {year}");
+        let name = template.render(Utc::now(), app.user.time_zone);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["old"]);
+        app.spotify.edit_playlist(SOURCE, |tracks| {
+            tracks[0].added_at = Utc::now() - chrono::Duration::days(800);
+        });
+        TestWatcher::new(playlist(SOURCE), PlaylistType::Template(template)).create(&app);
+
+        // Nothing was added this period, so there is no playlist to create yet
+        execute(app.ctx.clone()).await.unwrap();
+        assert!(app.spotify.playlist_named(&name).is_none());
+
+        app.spotify.edit_playlist(SOURCE, |tracks| tracks.push(FakeTrack::new("new")));
+        sync_again(&app).await;
+
+        let created = app.spotify.playlist_named(&name).unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(&created), vec!["new"]);
+        assert_eq!(app.spotify.playlist_track_ids(SOURCE), vec!["old", "new"]);
+    }
+
+    #[tokio::test]
+    async fn it_only_searches_for_the_playlist_of_a_period_once() {
+        let app = TestApp::start().await;
+        let template = template("Moved {year}-{month}");
+        let name = template.render(Utc::now(), app.user.time_zone);
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        TestWatcher::new(playlist(SOURCE), PlaylistType::Template(template)).create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+        sync_again(&app).await;
+        app.spotify.edit_playlist(SOURCE, |tracks| tracks.push(FakeTrack::new("b")));
+        sync_again(&app).await;
+
+        let created = app.spotify.playlist_named(&name).unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(&created), vec!["a", "b"]);
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/playlists"), 1);
+
+        // A deleted playlist is searched for again, and replaced once there are tracks to add
+        app.spotify.state().playlists.remove(&created);
+        app.spotify.edit_playlist(SOURCE, |tracks| tracks.push(FakeTrack::new("c")));
+        sync_again(&app).await;
+
+        let replaced = app.spotify.playlist_named(&name).unwrap();
+        assert_eq!(
+            app.spotify.playlist_track_ids(&replaced),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(app.spotify.request_count(&Method::GET, "/me/playlists"), 2);
+        let create_path = format!("/users/{}/playlists", TEST_USER_ID);
+        assert_eq!(app.spotify.request_count(&Method::POST, &create_path), 2);
+    }
+
+    #[tokio::test]
+    async fn it_evicts_the_oldest_tracks_from_a_full_target() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x", "y", "z"]);
+        let options = WatcherOptions {
+            max_tracks: Some(3),
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["z", "a", "b"]);
+
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let repo = TransferRepo::new(app.ctx.clone());
+        let transfer = repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap().remove(0);
+        assert_eq!(transfer.num_tracks_transferred, 2);
+
+        let evicted = repo
+            .get_tracks_for_transfer(transfer.id)
+            .unwrap()
+            .into_iter()
+            .filter(|track| track.action == TrackAction::Evicted)
+            .map(|track| (track.item_id.id().to_owned(), track.position))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            evicted,
+            vec![("x".to_owned(), Some(0)), ("y".to_owned(), Some(1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_moves_evicted_tracks_to_the_overflow_playlist() {
+        const OVERFLOW: &str = "OverflowPlaylist000000";
+
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x", "y", "z"]);
+        app.spotify.add_playlist(OVERFLOW, TEST_USER_ID, &["y"]);
+        let options = WatcherOptions {
+            max_tracks: Some(2),
+            eviction_policy: EvictionPolicy::Random,
+            overflow_playlist: Some(PlaylistId(OVERFLOW.to_owned())),
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .options(options)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        // The new track is never evicted, and the overflow playlist doesn't get a second copy of any track
+        let target = app.spotify.playlist_track_ids(TARGET);
+        assert_eq!(target.len(), 2);
+        assert!(target.contains(&"a".to_owned()));
+
+        let overflow = sorted(app.spotify.playlist_track_ids(OVERFLOW));
+        let mut expected = ["x", "y", "z"]
+            .into_iter()
+            .filter(|id| !target.contains(&id.to_string()))
+            .chain(["y"])
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(overflow, expected);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+
+        // Only the track added to the target was transferred
+        let watcher = WatcherRepo::new(app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        let transfer = TransferRepo::new(app.ctx.clone())
+            .get_transfers_for_watcher(watcher.id, 1, 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(transfer.num_tracks_transferred, 1);
+    }
+
+    #[tokio::test]
+    async fn it_moves_tracks_that_dont_fit_straight_to_the_overflow_playlist() {
+        const OVERFLOW: &str = "OverflowPlaylist000000";
+
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        app.spotify.edit_playlist(SOURCE, |tracks| {
+            for (index, track) in tracks.iter_mut().enumerate() {
+                track.added_at = Utc::now() - chrono::Duration::days(3 - index as i64);
+            }
+        });
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        app.spotify.add_playlist(OVERFLOW, TEST_USER_ID, &[]);
+        let options = WatcherOptions {
+            max_tracks: Some(2),
+            overflow_playlist: Some(PlaylistId(OVERFLOW.to_owned())),
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .should_remove(true)
+            .options(options)
+            .create(&app);
+
+        execute(app.ctx.clone()).await.unwrap();
+
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["b", "c"]);
+        assert_eq!(app.spotify.playlist_track_ids(OVERFLOW), vec!["a"]);
+        assert!(app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_keeps_a_copied_full_target_settled() {
+        let app = TestApp::start().await;
+        app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c", "d"]);
+        app.spotify.edit_playlist(SOURCE, |tracks| {
+            for (index, track) in tracks.iter_mut().enumerate() {
+                track.added_at = Utc::now() - chrono::Duration::days(4 - index as i64);
+            }
+        });
+        app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let options = WatcherOptions {
+            max_tracks: Some(2),
+            ..Default::default()
+        };
+        TestWatcher::new(playlist(SOURCE), playlist(TARGET))
+            .options(options)
+            .create(&app);
+
+        // Only the newest tracks are copied, and the older ones are never copied in their place. Each sync
+        // touches the source so the watcher plans again.
+        execute(app.ctx.clone()).await.unwrap();
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["c", "d"]);
+        for _ in 0..2 {
+            app.spotify.edit_playlist(SOURCE, |_| {});
+            sync_again(&app).await;
+            assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["c", "d"]);
+        }
+
+        // A new track evicts the oldest, which isn't copied back while the source still has it
+        app.spotify.edit_playlist(SOURCE, |tracks| tracks.push(FakeTrack::new("e")));
+        sync_again(&app).await;
+        assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["d", "e"]);
+        for _ in 0..2 {
+            app.spotify.edit_playlist(SOURCE, |_| {});
+            sync_again(&app).await;
+            assert_eq!(app.spotify.playlist_track_ids(TARGET), vec!["d", "e"]);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::{error::SyncResult, transfer::PlaylistTransfer};
+use crate::{
+    api::{
+        client::{Client, WithToken},
+        id::ItemId,
+    },
+    context::AppContext,
+    db::model::{
+        transfer_track::{TrackAction, TrackChange},
+        watcher::Watcher,
+    },
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Every change a sync would make for a watcher
+#[derive(Debug, Default, Serialize)]
+pub struct TransferPreview {
+    pub added: Vec<PreviewTrack>,
+    pub skipped_duplicates: Vec<PreviewTrack>,
+    pub removed: Vec<PreviewTrack>,
+    pub skipped_local: Vec<PreviewTrack>,
+    pub skipped_unsupported: Vec<PreviewTrack>,
+    pub evicted: Vec<PreviewTrack>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewTrack {
+    pub id: ItemId,
+    pub name: String,
+    pub artists: Vec<String>,
+    /// The playlist the track is added to, or for anything else the playlist it is in
+    pub playlist: String,
+    pub position: Option<u32>,
+}
+
+/// Plan a sync for a watcher without changing anything, looking up the name of every affected track.
+pub async fn preview_watcher(
+    ctx: AppContext,
+    client: Client<WithToken>,
+    watcher: &Watcher,
+    time_zone: Tz,
+) -> SyncResult<TransferPreview> {
+    let mut transfer = PlaylistTransfer::new(ctx, client.clone());
+    transfer.find_periods(watcher, Utc::now(), time_zone).await?;
+    let plan = transfer.plan(watcher).await?;
+
+    let mut seen = HashSet::new();
+    let ids = plan
+        .iter()
+        .map(|change| change.item_id.clone())
+        .filter(|id| seen.insert(id.clone()))
+        .collect::<Vec<_>>();
+    let names = get_names(&client, &ids).await?;
+
+    let mut preview = TransferPreview::default();
+
+    for TrackChange {
+        item_id,
+        action,
+        playlist_from,
+        playlist_to,
+        position,
+    } in plan
+    {
+        let (name, artists) =
+            names.get(&item_id).cloned().unwrap_or_else(|| ("Unknown track".into(), vec![]));
+        let playlist = match action {
+            TrackAction::Added => playlist_to,
+            _ => playlist_from,
+        };
+        let item = PreviewTrack {
+            id: item_id,
+            name,
+            artists,
+            playlist: playlist.to_value(),
+            position,
+        };
+
+        match action {
+            TrackAction::Added => preview.added.push(item),
+            TrackAction::SkippedDuplicate => preview.skipped_duplicates.push(item),
+            TrackAction::Removed => preview.removed.push(item),
+            TrackAction::SkippedLocal => preview.skipped_local.push(item),
+            TrackAction::SkippedUnsupported => preview.skipped_unsupported.push(item),
+            TrackAction::Evicted => preview.evicted.push(item),
+        }
+    }
+
+    Ok(preview)
+}
+
+/// Look up the name and artists of each item. Episodes list their show, and local files carry both in their URI.
+async fn get_names(
+    client: &Client<WithToken>,
+    ids: &[ItemId],
+) -> SyncResult<HashMap<ItemId, (String, Vec<String>)>> {
+    let track_ids = ids.iter().filter_map(ItemId::as_track).cloned().collect::<Vec<_>>();
+    let episode_ids = ids
+        .iter()
+        .filter_map(|id| match id {
+            ItemId::Episode(id) => Some(id.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut names = HashMap::new();
+
+    if !track_ids.is_empty() {
+        names.extend(client.tracks(&track_ids).await?.into_iter().map(|track| {
+            let artists = track.artists.into_iter().map(|artist| artist.name).collect();
+            (ItemId::Track(track.id), (track.name, artists))
+        }));
+    }
+
+    if !episode_ids.is_empty() {
+        names.extend(
+            client.episodes(&episode_ids).await?.into_iter().map(|episode| {
+                let shows = episode.show.into_iter().map(|show| show.name).collect();
+                (ItemId::Episode(episode.id), (episode.name, shows))
+            }),
+        );
+    }
+
+    names.extend(ids.iter().filter_map(|id| {
+        let (name, artist) = id.local_name()?;
+        Some((id.clone(), (name, vec![artist])))
+    }));
+
+    Ok(names)
+}
@@ -2,130 +2,1334 @@ use super::error::{SyncError, SyncResult};
 use crate::{
     api::{
         client::{Client, WithToken},
-        id::{PlaylistId, TrackId},
+        error::ClientError,
+        id::{ItemId, PlaylistId, SnapshotId, UserId},
+        model::{ItemEntry, SavedTracksSummary, TrackDetails},
     },
     context::AppContext,
-    db::model::{playlist::PlaylistType, watcher::Watcher},
+    db::{
+        model::{
+            journal::{BatchStatus, Journal, JournalBatch, PlannedBatch},
+            mirror::MirrorItem,
+            options::{EvictionPolicy, InsertOrder},
+            playlist::PlaylistType,
+            template::{PeriodPlaylist, PlaylistTemplate},
+            transfer_track::{TrackAction, TrackChange},
+            watcher::{PlaylistSnapshots, Watcher},
+        },
+        repo::{journal::JournalRepo, mirror::MirrorRepo, transfer::TransferRepo},
+    },
+};
+use chrono::{DateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use rand::seq::SliceRandom;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
 };
-use std::collections::HashSet;
+
+/// Most items a playlist can have added or removed in one request
+const PLAYLIST_BATCH_SIZE: usize = 100;
+
+/// Most tracks that can be saved to or removed from the user's saved tracks in one request
+const SAVED_BATCH_SIZE: usize = 50;
 
 pub struct PlaylistTransfer {
     ctx: AppContext,
     client: Client<WithToken>,
+    track_log: Vec<TrackChange>,
+    journal_id: Option<u32>,
+    snapshots: Option<PlaylistSnapshots>,
+    periods: HashMap<PlaylistTemplate, Period>,
+    /// Liked Tracks as read before planning, updated with the changes the transfer makes to it
+    saved_summary: Option<SavedTracksSummary>,
+    /// The fingerprint of each playlist the transfer changed, as of its own last change to it
+    written_snapshots: HashMap<PlaylistType, String>,
+}
+
+/// The playlist a templated target names for the period being synced
+struct Period {
+    name: String,
+    start: DateTime<Utc>,
+    /// `None` until the playlist exists, as it is only created once there are tracks to add to it
+    playlist: Option<PlaylistId>,
 }
 
 impl PlaylistTransfer {
     pub fn new(ctx: AppContext, client: Client<WithToken>) -> Self {
-        Self { ctx, client }
+        Self {
+            ctx,
+            client,
+            track_log: vec![],
+            journal_id: None,
+            snapshots: None,
+            periods: HashMap::new(),
+            saved_summary: None,
+            written_snapshots: HashMap::new(),
+        }
+    }
+
+    /// The journal of the transfer, if it got far enough to change any playlists
+    pub fn journal_id(&self) -> Option<u32> {
+        self.journal_id
+    }
+
+    /// The state of the watcher's playlists once the transfer finished, if it finished
+    pub fn snapshots(&self) -> Option<&PlaylistSnapshots> {
+        self.snapshots.as_ref()
+    }
+
+    /// Consume the transfer and return every track change it made
+    pub fn into_track_log(self) -> Vec<TrackChange> {
+        self.track_log
     }
 
     /// Using data from a watcher, attempt to transfer tracks from one playlist to another.
-    pub async fn try_transfer(&self, watcher: &Watcher) -> SyncResult<u32> {
+    ///
+    /// If neither playlist has changed since the watcher's last sync there is nothing to transfer, so
+    /// they aren't read at all.
+    ///
+    /// The planned changes are journaled before any are made, and each request is marked as done once
+    /// it succeeds, so a transfer interrupted by a crash can be finished with `resume`.
+    pub async fn try_transfer(
+        &mut self,
+        watcher: &Watcher,
+        synced_at: DateTime<Utc>,
+        time_zone: Tz,
+    ) -> SyncResult<u32> {
         if !self.ctx.config.sync.enabled {
             return Ok(0);
         }
 
-        if watcher.playlist_from == watcher.playlist_to {
-            return Err(SyncError::InvalidTransfer(
-                "cannot transfer to the same playlist".to_owned(),
-            ));
-        }
+        self.find_periods(watcher, synced_at, time_zone).await?;
 
-        // Get all tracks in source playlist and only continue if we have tracks to transfer
-        let ids_to_transfer = self.get_track_ids_to_transfer(&watcher.playlist_from).await?;
-        if ids_to_transfer.is_empty() {
+        let (sources, targets) = self.get_snapshots(watcher).await?;
+        let snapshots = join_snapshots(&sources, &targets);
+        if watcher.snapshots.as_ref() == Some(&snapshots) {
+            tracing::debug!("Playlists of watcher {} are unchanged", watcher.id);
+            self.snapshots = Some(snapshots);
             return Ok(0);
         }
 
-        // Transfer all tracks not already in target playlist
-        let num_transferred =
-            self.maybe_transfer_tracks(&watcher.playlist_to, &ids_to_transfer).await?;
+        let num_added = self.transfer(watcher, synced_at).await?;
 
-        // Remove all original tracks from source playlist
-        if watcher.should_remove {
-            let ids_to_remove = ids_to_transfer.into_iter().collect::<Vec<_>>();
-            self.remove_tracks_from_playlist(&watcher.playlist_from, &ids_to_remove).await?;
+        // Our own changes move the snapshots on, so those we got back are kept to avoid reading the
+        // playlists next time. The others aren't read again, as any change the user made since planning
+        // still has to be transferred.
+        let resolved = self.with_period_playlists(watcher);
+        let after = |playlists: Vec<&PlaylistType>, read: Vec<String>| {
+            playlists
+                .into_iter()
+                .zip(read)
+                .map(|(playlist, read)| {
+                    self.written_snapshots.get(playlist).cloned().unwrap_or(read)
+                })
+                .collect::<Vec<_>>()
+        };
+        self.snapshots = Some(join_snapshots(
+            &after(resolved.sources().collect(), sources),
+            &after(resolved.targets().collect(), targets),
+        ));
+
+        Ok(num_added)
+    }
+
+    /// Plan and journal the transfer, then make every change in it
+    async fn transfer(&mut self, watcher: &Watcher, synced_at: DateTime<Utc>) -> SyncResult<u32> {
+        let (mut plan, mirrored) = if watcher.options.mirror {
+            let (plan, mirrored) = self.plan_mirror(watcher).await?;
+            (plan, Some(mirrored))
+        } else {
+            (self.plan(watcher).await?, None)
+        };
+
+        // The journal only ever holds real playlists, so a resumed transfer doesn't depend on the period
+        self.create_period_playlists(watcher, &mut plan).await?;
+        let batches = plan_batches(&self.with_period_playlists(watcher), &plan);
+
+        if !batches.is_empty() {
+            self.journal_id = Some(
+                JournalRepo::new(self.ctx.clone())
+                    .create_journal(watcher.id, synced_at, &plan, &batches)?,
+            );
         }
 
-        Ok(num_transferred)
+        let num_added = self.run_journal(watcher, &plan, false).await?;
+
+        // A mirror interrupted part way leaves tracks either in both playlists or in neither, which the
+        // previous state already accounts for, so it is only replaced once every change has been made
+        if let Some(mirrored) = mirrored {
+            MirrorRepo::new(self.ctx.clone()).save_state(watcher.id, &mirrored)?;
+        }
+
+        Ok(num_added)
     }
 
-    /// Get the tracks IDs in the source playlist
-    async fn get_track_ids_to_transfer(
-        &self,
-        playlist_from: &PlaylistType,
-    ) -> SyncResult<HashSet<TrackId>> {
-        let ids = match playlist_from {
-            PlaylistType::Saved => self.client.current_user_saved_track_partials().await?,
-            PlaylistType::Id(id) => self.client.playlist_track_partials(id).await?,
+    /// Finish a transfer that was interrupted, skipping requests that had already succeeded.
+    ///
+    /// Additions are checked against the target first, as the tracks may have been added by a request
+    /// that was interrupted or by a later sync.
+    pub async fn resume(&mut self, watcher: &Watcher, journal: &Journal) -> SyncResult<u32> {
+        self.journal_id = Some(journal.id);
+        self.run_journal(watcher, &journal.plan, true).await
+    }
+
+    /// Send the journal's unfinished requests in order, additions before removals, and log the changes
+    /// made by each one. Returns the number of tracks added to the watcher's targets by the journal's
+    /// requests, as those moved to the overflow playlist were evicted rather than transferred.
+    async fn run_journal(
+        &mut self,
+        watcher: &Watcher,
+        plan: &[TrackChange],
+        reconcile: bool,
+    ) -> SyncResult<u32> {
+        let overflow = watcher.options.overflow_playlist.clone().map(PlaylistType::Id);
+        let repo = JournalRepo::new(self.ctx.clone());
+        let batches = match self.journal_id {
+            Some(journal_id) => repo.get_batches(journal_id)?,
+            None => vec![],
         };
+        let mut present = HashMap::<PlaylistType, HashMap<ItemId, usize>>::new();
+        let mut logged = HashMap::<(TrackAction, PlaylistType), usize>::new();
+        let mut num_added = 0;
 
-        Ok(ids.into_iter().map(|track| track.id).collect::<HashSet<_>>())
+        for journal_batch in &batches {
+            let batch = &journal_batch.batch;
+
+            // Skipped tracks are logged once every addition has been made, as before any removal
+            if batch.action != TrackAction::Added {
+                self.log_skipped(plan);
+            }
+
+            let sent = if journal_batch.status == BatchStatus::Done {
+                batch.items.len()
+            } else {
+                let items = match (&batch.action, reconcile) {
+                    (TrackAction::Added, true) => {
+                        if !present.contains_key(&batch.playlist) {
+                            let ids = self.get_item_ids(&batch.playlist).await?;
+                            let counts = count_unplanned(&batches, &batch.playlist, ids);
+                            present.insert(batch.playlist.clone(), counts);
+                        }
+
+                        // Each occurrence already in the target stands in for one that was planned, so
+                        // duplicates the plan asked for are still added
+                        let present = present.get_mut(&batch.playlist).expect("counted above");
+                        batch
+                            .items
+                            .iter()
+                            .filter(|id| match present.get_mut(id) {
+                                Some(count) if *count > 0 => {
+                                    *count -= 1;
+                                    false
+                                }
+                                _ => true,
+                            })
+                            .cloned()
+                            .collect()
+                    }
+                    _ => batch.items.clone(),
+                };
+
+                repo.update_batch_status(journal_batch.id, BatchStatus::Started)?;
+                self.send_batch(batch, &items).await?;
+                repo.update_batch_status(journal_batch.id, BatchStatus::Done)?;
+                items.len()
+            };
+
+            if batch.action == TrackAction::Added && Some(&batch.playlist) != overflow.as_ref() {
+                num_added += sent;
+            }
+
+            let logged = logged.entry((batch.action.clone(), batch.playlist.clone())).or_default();
+            self.log_batch(plan, batch, *logged);
+            *logged += batch.items.len();
+        }
+
+        self.log_skipped(plan);
+
+        Ok(num_added.try_into().expect("size cant possibly be bigger than u32"))
     }
 
-    /// Remove the tracks from the specified playlist by ID
-    async fn remove_tracks_from_playlist(
+    /// Look up the playlist each of the watcher's templated targets names for the period containing a
+    /// time, in the user's time zone. Must be called before planning a transfer to templated targets.
+    ///
+    /// The playlist found by the watcher's last sync is used for as long as its period lasts, so the
+    /// user's playlists are only searched once a new one starts.
+    pub async fn find_periods(
+        &mut self,
+        watcher: &Watcher,
+        at: DateTime<Utc>,
+        time_zone: Tz,
+    ) -> SyncResult<()> {
+        for template in templates(watcher) {
+            let name = template.render(at, time_zone);
+            let stored = watcher
+                .period_playlists
+                .iter()
+                .find(|period| period.template == *template && period.name == name);
+            let playlist = match stored {
+                Some(period) => Some(period.playlist.clone()),
+                None => self.find_period_playlist(watcher, &name).await?,
+            };
+
+            self.periods.insert(
+                template.clone(),
+                Period {
+                    name,
+                    start: template.period_start(at, time_zone),
+                    playlist,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Search the user's playlists for the one named for a period
+    async fn find_period_playlist(
         &self,
-        playlist_from: &PlaylistType,
-        ids_to_remove: &[TrackId],
+        watcher: &Watcher,
+        name: &str,
+    ) -> SyncResult<Option<PlaylistId>> {
+        let user_id = UserId::parse_from_input(&watcher.user_uri)?;
+        let playlist = self.client.current_user_playlist_by_name(&user_id, name).await?;
+
+        Ok(playlist.map(|playlist| playlist.id))
+    }
+
+    /// The playlist each of the watcher's templated targets resolved to, for the next sync to start from
+    pub fn period_playlists(&self, watcher: &Watcher) -> Vec<PeriodPlaylist> {
+        templates(watcher)
+            .filter_map(|template| {
+                let period = self.periods.get(template)?;
+                Some(PeriodPlaylist {
+                    template: template.clone(),
+                    name: period.name.clone(),
+                    playlist: period.playlist.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the period of a templated target, once it has been looked up
+    fn period(&self, template: &PlaylistTemplate) -> SyncResult<&Period> {
+        self.periods.get(template).ok_or_else(|| {
+            SyncError::InvalidTransfer(format!("no playlist was looked up for {}", template))
+        })
+    }
+
+    /// Create the playlist of each period that has tracks to add but doesn't exist yet, then point the
+    /// plan's changes at the playlist of each period that does
+    async fn create_period_playlists(
+        &mut self,
+        watcher: &Watcher,
+        plan: &mut [TrackChange],
     ) -> SyncResult<()> {
-        match playlist_from {
-            PlaylistType::Saved => {
-                self.client.current_user_saved_tracks_remove_ids(ids_to_remove).await?;
+        for template in templates(watcher) {
+            let target = PlaylistType::Template(template.clone());
+            let period = self.period(template)?;
+
+            if period.playlist.is_none()
+                && plan.iter().any(|change| {
+                    change.action == TrackAction::Added && change.playlist_to == target
+                })
+            {
+                let user_id = UserId::parse_from_input(&watcher.user_uri)?;
+                let description = format!("Created by modulate from \"{}\"", template);
+                let playlist =
+                    self.client.user_playlist_create(&user_id, &period.name, &description).await?;
+
+                tracing::info!(
+                    "Created playlist {} for watcher {}",
+                    period.name,
+                    watcher.id
+                );
+                if let Some(period) = self.periods.get_mut(template) {
+                    period.playlist = Some(playlist.id);
+                }
+            }
+
+            if let Some(id) = &self.period(template)?.playlist {
+                for change in plan.iter_mut().filter(|change| change.playlist_to == target) {
+                    change.playlist_to = PlaylistType::Id(id.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A copy of the watcher that transfers to the playlist of each period that exists
+    fn with_period_playlists(&self, watcher: &Watcher) -> Watcher {
+        let resolve = |playlist: &PlaylistType| match playlist {
+            PlaylistType::Template(template) => match self.periods.get(template) {
+                Some(Period {
+                    playlist: Some(id), ..
+                }) => PlaylistType::Id(id.clone()),
+                _ => playlist.clone(),
+            },
+            _ => playlist.clone(),
+        };
+
+        Watcher {
+            playlist_to: resolve(&watcher.playlist_to),
+            extra_targets: watcher.extra_targets.iter().map(resolve).collect(),
+            ..watcher.clone()
+        }
+    }
+
+    /// Make the request for a batch with the given items, and remember the fingerprint of the playlist
+    /// it leaves behind
+    async fn send_batch(&mut self, batch: &PlannedBatch, items: &[ItemId]) -> SyncResult<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let snapshot_ids = match (&batch.action, &batch.playlist) {
+            (TrackAction::Added, PlaylistType::Id(id)) => {
+                self.client.playlist_insert_ids(id, items, batch.position).await?
+            }
+            (TrackAction::Removed | TrackAction::Evicted, PlaylistType::Id(id)) => {
+                self.client.playlist_remove_ids(id, items).await?
+            }
+
+            // Saved tracks can only ever hold tracks
+            (TrackAction::Added, PlaylistType::Saved) => {
+                let ids = items.iter().filter_map(ItemId::as_track).cloned().collect::<Vec<_>>();
+                let saved_at = Utc::now();
+                self.client.current_user_saved_tracks_add_ids(&ids).await?;
+                self.update_saved_summary(|summary| {
+                    summary.total += ids.len() as u32;
+                    summary.newest_added_at = saved_at.with_nanosecond(0);
+                });
+                return Ok(());
             }
-            PlaylistType::Id(id) => {
-                self.client.playlist_remove_ids(id, ids_to_remove).await?;
+            (TrackAction::Removed | TrackAction::Evicted, PlaylistType::Saved) => {
+                let ids = items.iter().filter_map(ItemId::as_track).cloned().collect::<Vec<_>>();
+                self.client.current_user_saved_tracks_remove_ids(&ids).await?;
+                self.update_saved_summary(|summary| {
+                    summary.total = summary.total.saturating_sub(ids.len() as u32);
+                });
+                return Ok(());
+            }
+            (action, playlist) => {
+                return Err(SyncError::InvalidTransfer(format!(
+                    "cannot send {} batch to {}",
+                    action, playlist
+                )));
             }
         };
 
+        if let Some(SnapshotId(snapshot_id)) = snapshot_ids.into_iter().last() {
+            self.written_snapshots.insert(batch.playlist.clone(), snapshot_id);
+        }
+
         Ok(())
     }
 
-    /// Transfer the tracks to the target playlist by ID
-    async fn maybe_transfer_tracks(
+    /// Apply a change this transfer made to Liked Tracks to its summary, if it was read before planning.
+    ///
+    /// Spotify records when a track was saved as it saves it, so the time the request was sent stands in
+    /// for it, and removing tracks keeps the newest one unless it was among them. A wrong guess only
+    /// means the next sync reads Liked Tracks again.
+    fn update_saved_summary(&mut self, update: impl FnOnce(&mut SavedTracksSummary)) {
+        if let Some(summary) = &mut self.saved_summary {
+            update(summary);
+            self.written_snapshots.insert(PlaylistType::Saved, saved_snapshot(summary));
+        }
+    }
+
+    /// Work out every change a transfer would make for a watcher, without changing anything
+    pub async fn plan(&self, watcher: &Watcher) -> SyncResult<Vec<TrackChange>> {
+        if watcher.options.mirror {
+            return Ok(self.plan_mirror(watcher).await?.0);
+        }
+
+        check_playlists(watcher)?;
+        check_max_tracks(watcher)?;
+
+        // Get all tracks in the source playlists and only continue if we have tracks to transfer
+        let mut source = vec![];
+        for playlist in watcher.sources() {
+            source.extend(self.get_items_to_transfer(watcher, playlist).await?);
+        }
+        if source.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Local files can't be added through the API, so they are reported and left where they are
+        let mut plan = source
+            .iter()
+            .filter(|item| item.id.is_local())
+            .map(|item| {
+                change(
+                    item,
+                    TrackAction::SkippedLocal,
+                    &watcher.playlist_to,
+                    item.position,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Every target gets the tracks it is missing, so a track already in one target may still be added to another
+        let overflow = watcher.options.overflow_playlist.clone().map(PlaylistType::Id);
+        let mut inserted = HashSet::new();
+        let mut unsupported = HashSet::new();
+        let mut capped = HashSet::new();
+        for target in watcher.targets() {
+            // Get the tracks already in the target to prevent duplicates, and when they were added if the
+            // target is full and some of them may have to make room
+            let target_entries = match (target, watcher.options.max_tracks) {
+                (PlaylistType::Id(id), Some(_)) => {
+                    Some(self.client.playlist_item_entries(id).await?)
+                }
+                _ => None,
+            };
+            let target_ids = match &target_entries {
+                Some(entries) => entries.iter().map(|entry| entry.id.clone()).collect(),
+                None => self.get_item_ids(target).await?,
+            };
+
+            let mut to_insert = match target {
+                PlaylistType::Id(_) | PlaylistType::Template(_) => {
+                    get_items_to_insert(watcher, &source, &target_ids, watcher.options.insert_order)
+                }
+
+                // Liked Tracks is always listed newest first, so tracks are saved oldest first and end up
+                // in the order they were added to the source. Only tracks can be saved there.
+                PlaylistType::Saved => {
+                    let (tracks, episodes) =
+                        get_items_to_insert(watcher, &source, &target_ids, InsertOrder::Append)
+                            .into_iter()
+                            .partition::<Vec<_>, _>(|&index| source[index].id.as_track().is_some());
+
+                    plan.extend(episodes.iter().map(|&index| {
+                        let item = &source[index];
+                        change(item, TrackAction::SkippedUnsupported, target, item.position)
+                    }));
+                    unsupported.extend(episodes.into_iter().map(|index| &source[index].id));
+
+                    tracks
+                }
+            };
+
+            // Each period's playlist only gets the tracks added during it, unless the source is being emptied
+            if let PlaylistType::Template(template) = target
+                && !watcher.should_remove
+            {
+                let start = self.period(template)?.start;
+                to_insert.retain(|&index| source[index].added_at.is_some_and(|at| at >= start));
+            }
+
+            // A capped target only takes the newest tracks, making room for them by evicting others
+            let mut evictions = vec![];
+            if let (Some(entries), Some(max_tracks)) = (&target_entries, watcher.options.max_tracks)
+            {
+                let max_tracks = max_tracks as usize;
+                let evicted_at =
+                    TransferRepo::new(self.ctx.clone()).get_eviction_times(watcher.id, target)?;
+                let (kept, left_out) =
+                    get_items_to_cap(&source, to_insert, &evicted_at, max_tracks);
+                to_insert = kept;
+                capped.extend(left_out);
+
+                let added = to_insert.iter().map(|&index| &source[index].id).collect::<Vec<_>>();
+                evictions = get_items_to_evict(
+                    entries,
+                    &added,
+                    max_tracks,
+                    watcher.options.eviction_policy,
+                );
+
+                // Tracks that can't be evicted, like local files, may leave too little room for the rest
+                let evicted_ids =
+                    evictions.iter().map(|&index| &entries[index].id).collect::<HashSet<_>>();
+                let remaining =
+                    entries.iter().filter(|entry| !evicted_ids.contains(&entry.id)).count();
+                capped.extend(trim_to_room(
+                    &source,
+                    &mut to_insert,
+                    max_tracks.saturating_sub(remaining),
+                ));
+            }
+
+            plan.extend(to_insert.iter().enumerate().map(|(index, &source_index)| {
+                let target_position = match (target, watcher.options.insert_order) {
+                    (PlaylistType::Saved, _) => to_insert.len() - 1 - index,
+                    (_, InsertOrder::Prepend) => index,
+                    _ => target_ids.len() + index,
+                };
+
+                change(
+                    &source[source_index],
+                    TrackAction::Added,
+                    target,
+                    target_position,
+                )
+            }));
+
+            if let Some(entries) = &target_entries {
+                plan.extend(evictions.into_iter().map(|index| TrackChange {
+                    item_id: entries[index].id.clone(),
+                    action: TrackAction::Evicted,
+                    playlist_from: target.clone(),
+                    playlist_to: overflow.clone().unwrap_or_else(|| target.clone()),
+                    position: Some(
+                        index.try_into().expect("size cant possibly be bigger than u32"),
+                    ),
+                }));
+            }
+
+            inserted.extend(to_insert);
+        }
+
+        // A source being emptied can't keep the tracks no target had room for, so they are evicted from it
+        // as they arrive
+        let inserted_ids = inserted.iter().map(|&index| &source[index].id).collect::<HashSet<_>>();
+        let evicted_on_arrival = if watcher.should_remove {
+            capped
+                .iter()
+                .map(|&index| &source[index].id)
+                .filter(|id| !inserted_ids.contains(id))
+                .collect::<HashSet<_>>()
+        } else {
+            HashSet::new()
+        };
+        let mut seen = HashSet::new();
+        plan.extend(
+            source
+                .iter()
+                .filter(|item| evicted_on_arrival.contains(&item.id))
+                .filter(|item| seen.insert((&item.playlist, &item.id)))
+                .map(|item| {
+                    let target = overflow.as_ref().unwrap_or(&watcher.playlist_to);
+                    change(item, TrackAction::Evicted, target, item.position)
+                }),
+        );
+
+        // Evicted tracks are moved to the overflow playlist, unless it already has them
+        if let Some(overflow) = &watcher.options.overflow_playlist
+            && plan.iter().any(|change| change.action == TrackAction::Evicted)
+        {
+            let overflow_ids = self.client.playlist_item_ids(overflow).await?;
+            let mut seen = overflow_ids.iter().collect::<HashSet<_>>();
+
+            let to_overflow = plan
+                .iter()
+                .filter(|change| change.action == TrackAction::Evicted)
+                .filter(|change| seen.insert(&change.item_id))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            plan.extend(to_overflow.into_iter().enumerate().map(|(index, change)| {
+                TrackChange {
+                    action: TrackAction::Added,
+                    position: Some(
+                        (overflow_ids.len() + index)
+                            .try_into()
+                            .expect("size cant possibly be bigger than u32"),
+                    ),
+                    ..change
+                }
+            }));
+        }
+
+        if watcher.should_remove {
+            // Tracks that were already in every target will leave the source without being added, while
+            // those a target can't hold stay where they are
+            let transferable = source.iter().enumerate().filter(|(_, item)| {
+                !item.id.is_local()
+                    && !unsupported.contains(&item.id)
+                    && !evicted_on_arrival.contains(&item.id)
+            });
+
+            plan.extend(
+                transferable.clone().filter(|(index, _)| !inserted.contains(index)).map(
+                    |(_, item)| {
+                        change(
+                            item,
+                            TrackAction::SkippedDuplicate,
+                            &watcher.playlist_to,
+                            item.position,
+                        )
+                    },
+                ),
+            );
+
+            // Removing a track removes every occurrence of it, so each is only removed once per source
+            let mut seen = HashSet::new();
+            plan.extend(
+                transferable.filter(|(_, item)| seen.insert((&item.playlist, &item.id))).map(
+                    |(_, item)| {
+                        change(
+                            item,
+                            TrackAction::Removed,
+                            &watcher.playlist_to,
+                            item.position,
+                        )
+                    },
+                ),
+            );
+        }
+
+        Ok(plan)
+    }
+
+    /// Work out the changes that make both of a mirror's playlists hold the same tracks, along with the
+    /// state they will be in once they do.
+    ///
+    /// A track that both playlists held after the last sync but one is now missing was removed from it,
+    /// so it is removed from the other too. Any other track in only one of them was added to it, and is
+    /// copied to the other. Tracks the mirror can't tell either way are settled by its conflict policy.
+    async fn plan_mirror(
         &self,
-        playlist_to: &PlaylistType,
-        ids_to_transfer: &HashSet<TrackId>,
-    ) -> SyncResult<u32> {
-        match playlist_to {
-            PlaylistType::Id(to_id) => {
-                // Get the tracks already in the target playlist to prevent duplicates
-                let playlist_track_ids = self.get_playlist_track_ids(to_id).await?;
-
-                // Get only the saved tracks that are not already in the target playlist and add them
-                let ids_to_insert = self.get_ids_to_insert(ids_to_transfer, &playlist_track_ids);
-                if !ids_to_insert.is_empty() {
-                    self.client.playlist_add_ids(to_id, &ids_to_insert).await?;
+        watcher: &Watcher,
+    ) -> SyncResult<(Vec<TrackChange>, Vec<MirrorItem>)> {
+        check_playlists(watcher)?;
+        check_mirror(watcher)?;
+
+        let state = MirrorRepo::new(self.ctx.clone()).get_state(watcher.id)?;
+        let known = state.as_ref().map(|state| {
+            state.items.iter().map(|item| (&item.id, item)).collect::<HashMap<_, _>>()
+        });
+
+        let source = self.get_items_to_transfer(watcher, &watcher.playlist_from).await?;
+        let target = self.get_items_to_transfer(watcher, &watcher.playlist_to).await?;
+        let source_added = latest_added_at(&source);
+        let target_added = latest_added_at(&target);
+
+        let mut plan = vec![];
+        let mut removed = vec![];
+        let mut mirrored = vec![];
+
+        let sides = [
+            (true, &source, &source_added, &target, &target_added),
+            (false, &target, &target_added, &source, &source_added),
+        ];
+        for (in_source, items, added, other_items, other_added) in sides {
+            let other = match in_source {
+                true => &watcher.playlist_to,
+                false => &watcher.playlist_from,
+            };
+
+            let mut seen = HashSet::new();
+            let mut copies = vec![];
+            for item in items
+                .iter()
+                .filter(|item| !other_added.contains_key(&item.id) && seen.insert(&item.id))
+            {
+                if item.id.is_local() {
+                    plan.push(change(
+                        item,
+                        TrackAction::SkippedLocal,
+                        other,
+                        item.position,
+                    ));
+                } else if mirror_keeps(
+                    watcher,
+                    known.as_ref(),
+                    &item.id,
+                    added[&item.id],
+                    in_source,
+                ) {
+                    copies.push(item.clone());
+                } else {
+                    removed.push(change(item, TrackAction::Removed, other, item.position));
                 }
+            }
 
-                Ok(ids_to_insert.len().try_into().expect("size cant possibly be bigger than u32"))
+            let to_insert =
+                get_items_to_insert(watcher, &copies, &[], watcher.options.insert_order);
+            plan.extend(to_insert.iter().enumerate().map(|(index, &copy_index)| {
+                let position = match watcher.options.insert_order {
+                    InsertOrder::Prepend => index,
+                    _ => other_items.len() + index,
+                };
+                change(&copies[copy_index], TrackAction::Added, other, position)
+            }));
+
+            mirrored.extend(copies.iter().map(|item| MirrorItem {
+                id: item.id.clone(),
+                source_added_at: added[&item.id].filter(|_| in_source),
+                target_added_at: added[&item.id].filter(|_| !in_source),
+            }));
+        }
+
+        // Tracks already in both playlists stay there
+        let mut seen = HashSet::new();
+        mirrored.extend(
+            source
+                .iter()
+                .filter(|item| {
+                    !item.id.is_local()
+                        && target_added.contains_key(&item.id)
+                        && seen.insert(&item.id)
+                })
+                .map(|item| MirrorItem {
+                    id: item.id.clone(),
+                    source_added_at: source_added[&item.id],
+                    target_added_at: target_added[&item.id],
+                }),
+        );
+
+        plan.extend(removed);
+
+        Ok((plan, mirrored))
+    }
+
+    /// Get the items in a source playlist that match the watcher's rules, in playlist order
+    async fn get_items_to_transfer(
+        &self,
+        watcher: &Watcher,
+        playlist: &PlaylistType,
+    ) -> SyncResult<Vec<SourceItem>> {
+        if watcher.rules.is_empty() && !watcher.options.insert_order.needs_details() {
+            // Without rules or sorting we only need the IDs, which is a much smaller response
+            let entries = match playlist {
+                PlaylistType::Saved => self.client.current_user_saved_item_entries().await?,
+                PlaylistType::Id(id) => self.client.playlist_item_entries(id).await?,
+                PlaylistType::Template(_) => return Err(template_source_error()),
+            };
+
+            return Ok(entries
+                .into_iter()
+                .enumerate()
+                .map(|(position, entry)| SourceItem {
+                    id: entry.id,
+                    playlist: playlist.clone(),
+                    position,
+                    added_at: entry.added_at,
+                    details: None,
+                })
+                .collect());
+        }
+
+        let now = Utc::now();
+
+        Ok(match playlist {
+            PlaylistType::Saved => self.client.current_user_saved_track_details().await?,
+            PlaylistType::Id(id) => self.client.playlist_track_details(id).await?,
+            PlaylistType::Template(_) => return Err(template_source_error()),
+        }
+        .into_iter()
+        .enumerate()
+        .filter(|(_, item)| watcher.rules.matches(item, now))
+        .map(|(position, item)| SourceItem {
+            id: item.id,
+            playlist: playlist.clone(),
+            position,
+            added_at: item.added_at,
+            details: Some(item.track),
+        })
+        .collect())
+    }
+
+    /// Fetch a fingerprint of each of the watcher's sources and targets, with one request each
+    async fn get_snapshots(&mut self, watcher: &Watcher) -> SyncResult<(Vec<String>, Vec<String>)> {
+        let mut sources = vec![];
+        for playlist in watcher.sources() {
+            sources.push(self.get_snapshot(watcher, playlist).await?);
+        }
+
+        let mut targets = vec![];
+        for playlist in watcher.targets() {
+            targets.push(self.get_snapshot(watcher, playlist).await?);
+        }
+
+        Ok((sources, targets))
+    }
+
+    /// Fetch a fingerprint of a playlist that changes whenever its tracks do
+    async fn get_snapshot(
+        &mut self,
+        watcher: &Watcher,
+        playlist: &PlaylistType,
+    ) -> SyncResult<String> {
+        Ok(match playlist {
+            PlaylistType::Id(id) => self.client.playlist_snapshot_id(id).await?.0,
+
+            PlaylistType::Saved => {
+                let summary = self.client.current_user_saved_tracks_summary().await?;
+                let snapshot = saved_snapshot(&summary);
+                self.saved_summary = Some(summary);
+                snapshot
             }
 
-            // We don't want to support transferring to saved tracks (for now; I just don't see the point)
-            PlaylistType::Saved => Err(SyncError::InvalidTransfer(
-                "cannot transfer to saved tracks".to_owned(),
+            // A new period changes which playlist this is, even before anything has been added to it
+            PlaylistType::Template(template) => {
+                let Period { name, playlist, .. } = self.period(template)?;
+                let (name, stored) = (name.clone(), playlist.clone());
+
+                let Some(stored) = stored else {
+                    return Ok(format!("new:{}", name));
+                };
+                match self.client.playlist_snapshot_id(&stored).await {
+                    Ok(SnapshotId(snapshot_id)) => snapshot_id,
+
+                    // The playlist found for the period was deleted since, so it is looked for by name again
+                    Err(ClientError::ApiError { status: 404, .. }) => {
+                        let playlist = self.find_period_playlist(watcher, &name).await?;
+                        let snapshot = match &playlist {
+                            Some(id) => self.client.playlist_snapshot_id(id).await?.0,
+                            None => format!("new:{}", name),
+                        };
+                        if let Some(period) = self.periods.get_mut(template) {
+                            period.playlist = playlist;
+                        }
+                        snapshot
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        })
+    }
+
+    /// Fetch the IDs in the specified playlist, in playlist order
+    async fn get_item_ids(&self, playlist: &PlaylistType) -> SyncResult<Vec<ItemId>> {
+        Ok(match playlist {
+            PlaylistType::Saved => self.client.current_user_saved_item_ids().await?,
+            PlaylistType::Id(id) => self.client.playlist_item_ids(id).await?,
+            PlaylistType::Template(template) => match &self.period(template)?.playlist {
+                Some(id) => self.client.playlist_item_ids(id).await?,
+                None => vec![],
+            },
+        })
+    }
+
+    /// Record the planned changes made by a batch once it has been sent. Batches split the planned
+    /// changes for their action and playlist in order, so a batch makes the changes following those
+    /// of the batches before it.
+    fn log_batch(&mut self, plan: &[TrackChange], batch: &PlannedBatch, offset: usize) {
+        self.track_log.extend(
+            plan.iter()
+                .filter(|change| {
+                    change.action == batch.action && batch_playlist(change) == &batch.playlist
+                })
+                .skip(offset)
+                .take(batch.items.len())
+                .cloned(),
+        );
+    }
+
+    /// Record the tracks that were planned to be skipped, once
+    fn log_skipped(&mut self, plan: &[TrackChange]) {
+        let skipped = |action: &TrackAction| {
+            matches!(
+                action,
+                TrackAction::SkippedDuplicate
+                    | TrackAction::SkippedLocal
+                    | TrackAction::SkippedUnsupported
+            )
+        };
+
+        if self.track_log.iter().any(|change| skipped(&change.action)) {
+            return;
+        }
+
+        // Duplicates are logged before local files, as they always have been
+        for action in [
+            TrackAction::SkippedDuplicate,
+            TrackAction::SkippedLocal,
+            TrackAction::SkippedUnsupported,
+        ] {
+            self.track_log
+                .extend(plan.iter().filter(|change| change.action == action).cloned());
+        }
+    }
+}
+
+/// An item in one of the source playlists that a watcher may transfer
+#[derive(Clone)]
+struct SourceItem {
+    id: ItemId,
+    /// The source playlist the item is in, and where
+    playlist: PlaylistType,
+    position: usize,
+    added_at: Option<DateTime<Utc>>,
+    /// Only fetched when the watcher's rules or insert order need them
+    details: Option<TrackDetails>,
+}
+
+/// Find the source items that are not in the target yet, returning their indices in `source` in the
+/// order they should be inserted. Tracks in more than one source are only inserted once.
+fn get_items_to_insert(
+    watcher: &Watcher,
+    source: &[SourceItem],
+    target: &[ItemId],
+    order: InsertOrder,
+) -> Vec<usize> {
+    let mut candidates = source
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| !item.id.is_local())
+        .collect::<Vec<_>>();
+
+    // Oldest first, as playlist items may have been moved since they were added. Saved tracks are
+    // listed newest first, so those saved at the same moment are taken from the bottom up.
+    candidates.sort_by(|(_, a), (_, b)| {
+        a.added_at.cmp(&b.added_at).then_with(|| match &a.playlist {
+            PlaylistType::Saved if b.playlist == PlaylistType::Saved => b.position.cmp(&a.position),
+            _ => Ordering::Equal,
+        })
+    });
+
+    if !watcher.options.keep_duplicates {
+        let mut seen = HashSet::new();
+        candidates.retain(|(_, item)| seen.insert(&item.id));
+    }
+
+    // Each occurrence of a track in the target stands in for one occurrence in the source
+    let mut in_target = HashMap::<&ItemId, usize>::new();
+    for id in target {
+        *in_target.entry(id).or_default() += 1;
+    }
+
+    candidates.retain(|(_, item)| match in_target.get_mut(&item.id) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            false
+        }
+        _ => true,
+    });
+
+    match order {
+        InsertOrder::Append => {}
+        // Each insert goes after the last, so the newest track is inserted first to end up at the top
+        InsertOrder::Prepend => candidates.reverse(),
+        order => candidates.sort_by_cached_key(|(_, item)| {
+            item.details.as_ref().and_then(|details| order.sort_key(details))
+        }),
+    }
+
+    candidates.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Count the occurrences of each item in a playlist that the journal's finished additions to it don't
+/// account for, as those may still be waiting for one of its unfinished additions
+fn count_unplanned(
+    batches: &[JournalBatch],
+    playlist: &PlaylistType,
+    ids: Vec<ItemId>,
+) -> HashMap<ItemId, usize> {
+    let mut counts = HashMap::<ItemId, usize>::new();
+    for id in ids {
+        *counts.entry(id).or_default() += 1;
+    }
+
+    let done = batches.iter().filter(|journal_batch| {
+        journal_batch.status == BatchStatus::Done
+            && journal_batch.batch.action == TrackAction::Added
+            && journal_batch.batch.playlist == *playlist
+    });
+    for id in done.flat_map(|journal_batch| &journal_batch.batch.items) {
+        if let Some(count) = counts.get_mut(id) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    counts
+}
+
+/// Whether a mirror copies a track that is only in one of its playlists to the other, rather than
+/// removing it from the one it is in
+fn mirror_keeps(
+    watcher: &Watcher,
+    known: Option<&HashMap<&ItemId, &MirrorItem>>,
+    id: &ItemId,
+    added_at: Option<DateTime<Utc>>,
+    in_source: bool,
+) -> bool {
+    let policy = watcher.options.conflict_policy;
+
+    // Without a previous sync there's no telling which playlist changed
+    let Some(known) = known else {
+        return policy.keeps(in_source);
+    };
+
+    // Neither playlist had the track last time, so it has been added since
+    let Some(item) = known.get(id) else {
+        return true;
+    };
+
+    // Both had it, so it was removed from the other playlist, unless it has been added to this one again
+    let recorded = match in_source {
+        true => item.source_added_at,
+        false => item.target_added_at,
+    };
+    match recorded {
+        Some(recorded) if Some(recorded) != added_at => policy.keeps(in_source),
+        _ => false,
+    }
+}
+
+/// When each item in a playlist was last added to it
+fn latest_added_at(items: &[SourceItem]) -> HashMap<&ItemId, Option<DateTime<Utc>>> {
+    let mut latest = HashMap::new();
+    for item in items {
+        let added_at = latest.entry(&item.id).or_insert(item.added_at);
+        *added_at = (*added_at).max(item.added_at);
+    }
+
+    latest
+}
+
+/// Split the additions and removals in a plan into the requests that will make them. Every playlist
+/// gets its additions before anything is removed from one.
+fn plan_batches(watcher: &Watcher, plan: &[TrackChange]) -> Vec<PlannedBatch> {
+    let batches = |action: TrackAction, playlist: &PlaylistType, size: usize| {
+        let ids = plan
+            .iter()
+            .filter(|change| change.action == action && batch_playlist(change) == playlist)
+            .map(|change| change.item_id.clone())
+            .collect::<Vec<_>>();
+
+        ids.chunks(size)
+            .enumerate()
+            .map(|(index, items)| PlannedBatch {
+                position: (action == TrackAction::Added
+                    && watcher.options.insert_order == InsertOrder::Prepend
+                    && *playlist != PlaylistType::Saved)
+                    .then(|| {
+                        (index * size).try_into().expect("size cant possibly be bigger than u32")
+                    }),
+                action: action.clone(),
+                playlist: playlist.clone(),
+                items: items.to_vec(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Mirrors add to and remove from both of their playlists, so every playlist is checked for both
+    let overflow = watcher.options.overflow_playlist.clone().map(PlaylistType::Id);
+    let playlists = || watcher.targets().chain(watcher.sources()).chain(overflow.as_ref());
+    let size = |playlist: &PlaylistType| match playlist {
+        PlaylistType::Saved => SAVED_BATCH_SIZE,
+        PlaylistType::Id(_) | PlaylistType::Template(_) => PLAYLIST_BATCH_SIZE,
+    };
+    let added =
+        playlists().flat_map(|playlist| batches(TrackAction::Added, playlist, size(playlist)));
+    let removed =
+        playlists().flat_map(|playlist| batches(TrackAction::Removed, playlist, size(playlist)));
+    let evicted = watcher
+        .targets()
+        .chain(watcher.sources())
+        .flat_map(|playlist| batches(TrackAction::Evicted, playlist, size(playlist)));
+
+    added.chain(removed).chain(evicted).collect()
+}
+
+/// The playlist whose batch makes a change: the playlist a track is removed or evicted from, and the
+/// target for anything else
+fn batch_playlist(change: &TrackChange) -> &PlaylistType {
+    match change.action {
+        TrackAction::Removed | TrackAction::Evicted => &change.playlist_from,
+        _ => &change.playlist_to,
+    }
+}
+
+/// Make sure no playlist is both read from and written to, or used twice, by a watcher
+fn check_playlists(watcher: &Watcher) -> SyncResult<()> {
+    let mut seen = HashSet::new();
+
+    if !watcher.sources().chain(watcher.targets()).all(|playlist| seen.insert(playlist)) {
+        return Err(SyncError::InvalidTransfer(
+            "cannot transfer to the same playlist".to_owned(),
+        ));
+    }
+
+    if watcher.sources().any(|playlist| matches!(playlist, PlaylistType::Template(_))) {
+        return Err(template_source_error());
+    }
+
+    Ok(())
+}
+
+/// Only playlists can be capped, as tracks can't be evicted from Liked Tracks nor a playlist that may not
+/// exist yet, and evicted tracks have to leave the watcher's playlists
+fn check_max_tracks(watcher: &Watcher) -> SyncResult<()> {
+    if watcher.options.max_tracks.is_none() {
+        return match watcher.options.overflow_playlist {
+            Some(_) => Err(SyncError::InvalidTransfer(
+                "cannot move evicted tracks without a maximum number of tracks".to_owned(),
             )),
+            None => Ok(()),
+        };
+    }
+
+    if !watcher.targets().all(|playlist| matches!(playlist, PlaylistType::Id(_))) {
+        return Err(SyncError::InvalidTransfer(
+            "can only limit the number of tracks in a playlist".to_owned(),
+        ));
+    }
+
+    if let Some(overflow) = &watcher.options.overflow_playlist {
+        let overflow = PlaylistType::Id(overflow.clone());
+        if watcher.sources().chain(watcher.targets()).any(|playlist| *playlist == overflow) {
+            return Err(SyncError::InvalidTransfer(
+                "cannot move evicted tracks to one of the watcher's playlists".to_owned(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The error for a watcher that transfers from a templated playlist, which only names a target
+fn template_source_error() -> SyncError {
+    SyncError::InvalidTransfer("cannot transfer from a playlist template".to_owned())
+}
+
+/// The templates naming the watcher's targets, in order
+fn templates(watcher: &Watcher) -> impl Iterator<Item = &PlaylistTemplate> {
+    watcher.targets().filter_map(|playlist| match playlist {
+        PlaylistType::Template(template) => Some(template),
+        _ => None,
+    })
+}
+
+/// Mirrors keep every track in both of their playlists, so they can't filter tracks, and only ever
+/// mirror two of the user's playlists
+fn check_mirror(watcher: &Watcher) -> SyncResult<()> {
+    if !watcher.rules.is_empty() {
+        return Err(SyncError::InvalidTransfer(
+            "cannot mirror playlists with filter rules".to_owned(),
+        ));
+    }
+
+    if !watcher.extra_sources.is_empty() || !watcher.extra_targets.is_empty() {
+        return Err(SyncError::InvalidTransfer(
+            "cannot mirror more than two playlists".to_owned(),
+        ));
+    }
+
+    if watcher.playlist_from == PlaylistType::Saved || watcher.playlist_to == PlaylistType::Saved {
+        return Err(SyncError::InvalidTransfer(
+            "cannot mirror saved tracks".to_owned(),
+        ));
+    }
+
+    if matches!(watcher.playlist_to, PlaylistType::Template(_)) {
+        return Err(SyncError::InvalidTransfer(
+            "cannot mirror a playlist template".to_owned(),
+        ));
+    }
+
+    if watcher.options.max_tracks.is_some() {
+        return Err(SyncError::InvalidTransfer(
+            "cannot limit the number of tracks in a mirror".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Narrow the additions to a target holding at most `max_tracks` down to the newest `max_tracks` source
+/// items, leaving out any the watcher has evicted from it since they were added to the source. Returns
+/// the additions to keep, in order, and those left out.
+fn get_items_to_cap(
+    source: &[SourceItem],
+    to_insert: Vec<usize>,
+    evicted_at: &HashMap<ItemId, DateTime<Utc>>,
+    max_tracks: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    // Items without a date were added before Spotify recorded one, so they are the oldest
+    let mut by_age = source.iter().filter(|item| !item.id.is_local()).collect::<Vec<_>>();
+    by_age.sort_by_key(|item| Reverse(item.added_at));
+
+    let mut newest = HashSet::new();
+    for item in by_age {
+        if newest.len() == max_tracks {
+            break;
         }
+        newest.insert(&item.id);
     }
 
-    /// Fetch the unique IDs in the specified playlist
-    async fn get_playlist_track_ids(&self, playlist: &PlaylistId) -> SyncResult<HashSet<TrackId>> {
-        Ok(self
-            .client
-            .playlist_track_partials(playlist)
-            .await?
-            .into_iter()
-            .map(|track| track.id)
-            .collect::<HashSet<_>>())
+    // A track added to the source again after it was evicted may come back
+    to_insert.into_iter().partition(|&index| {
+        let item = &source[index];
+        newest.contains(&item.id)
+            && evicted_at
+                .get(&item.id)
+                .is_none_or(|at| item.added_at.is_some_and(|added_at| added_at > *at))
+    })
+}
+
+/// Drop the oldest additions to a target with room for only `room` more, returning those dropped
+fn trim_to_room(source: &[SourceItem], to_insert: &mut Vec<usize>, room: usize) -> Vec<usize> {
+    let mut by_age = to_insert.clone();
+    by_age.sort_by_key(|&index| source[index].added_at);
+
+    let dropped = by_age[..by_age.len().saturating_sub(room)].to_vec();
+    to_insert.retain(|index| !dropped.contains(index));
+    dropped
+}
+
+/// Pick the tracks to evict from a target so it holds no more than `max_tracks` once `added` have been
+/// added to it, returning their indices in `entries`. Tracks being added by this sync are never evicted,
+/// so the additions have to be trimmed to fit beforehand.
+fn get_items_to_evict(
+    entries: &[ItemEntry],
+    added: &[&ItemId],
+    max_tracks: usize,
+    policy: EvictionPolicy,
+) -> Vec<usize> {
+    let mut excess = (entries.len() + added.len()).saturating_sub(max_tracks);
+    let added = added.iter().collect::<HashSet<_>>();
+
+    // Tracks without a date were added before Spotify recorded one, so they are the oldest
+    let mut order = (0..entries.len()).collect::<Vec<_>>();
+    match policy {
+        EvictionPolicy::Oldest => order.sort_by_key(|&index| entries[index].added_at),
+        EvictionPolicy::Random => order.shuffle(&mut rand::rng()),
+    }
+
+    let mut seen = HashSet::new();
+    let mut evicted = vec![];
+    for index in order {
+        if excess == 0 {
+            break;
+        }
+
+        let id = &entries[index].id;
+        if id.is_local() || added.contains(&id) || !seen.insert(id) {
+            continue;
+        }
+
+        // Removing a track removes every occurrence of it, so each one makes room
+        excess = excess.saturating_sub(entries.iter().filter(|entry| entry.id == *id).count());
+        evicted.push(index);
     }
 
-    /// Find the IDs that are not in the target playlist, and return them reversed so they may be inserted in the correct order
-    fn get_ids_to_insert(&self, from: &HashSet<TrackId>, to: &HashSet<TrackId>) -> Vec<TrackId> {
-        let mut ids_to_insert = from.difference(to).cloned().collect::<Vec<_>>();
+    evicted
+}
+
+/// Combine the fingerprints of a watcher's playlists, in order
+fn join_snapshots(sources: &[String], targets: &[String]) -> PlaylistSnapshots {
+    PlaylistSnapshots {
+        source: sources.join(" "),
+        target: targets.join(" "),
+    }
+}
 
-        // Since we read them in order from newest to oldest, we want to insert them oldest first so we retain this order
-        ids_to_insert.reverse();
+/// Liked Tracks has no snapshot ID, but saving or removing a track changes its summary
+fn saved_snapshot(summary: &SavedTracksSummary) -> String {
+    let newest = summary.newest_added_at.map(|at| at.to_rfc3339()).unwrap_or_default();
+    format!("{}:{}", summary.total, newest)
+}
 
-        ids_to_insert
+/// Describe a change to an item from a source, made on behalf of a watcher
+fn change(
+    item: &SourceItem,
+    action: TrackAction,
+    target: &PlaylistType,
+    position: usize,
+) -> TrackChange {
+    TrackChange {
+        item_id: item.id.clone(),
+        action,
+        playlist_from: item.playlist.clone(),
+        playlist_to: target.clone(),
+        position: Some(position.try_into().expect("size cant possibly be bigger than u32")),
     }
 }
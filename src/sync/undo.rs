@@ -0,0 +1,205 @@
+use super::error::{SyncError, SyncResult};
+use crate::{
+    api::{
+        client::{Client, WithToken},
+        id::ItemId,
+    },
+    context::AppContext,
+    db::{
+        model::{
+            playlist::PlaylistType,
+            transfer::Transfer,
+            transfer_track::{TrackAction, TrackChange, TransferTrack},
+        },
+        repo::transfer::TransferRepo,
+    },
+};
+use chrono::Utc;
+use std::collections::HashSet;
+
+pub struct TransferUndo {
+    ctx: AppContext,
+    client: Client<WithToken>,
+    track_log: Vec<TrackChange>,
+}
+
+impl TransferUndo {
+    pub fn new(ctx: AppContext, client: Client<WithToken>) -> Self {
+        Self {
+            ctx,
+            client,
+            track_log: vec![],
+        }
+    }
+
+    /// Consume the undo and return every track change it made
+    pub fn into_track_log(self) -> Vec<TrackChange> {
+        self.track_log
+    }
+
+    /// Revert the track changes recorded for a transfer, returning the number of tracks restored to the source.
+    pub async fn try_undo(&mut self, transfer: &Transfer) -> SyncResult<u32> {
+        let repo = TransferRepo::new(self.ctx.clone());
+
+        if transfer.undo_of.is_some() {
+            return Err(SyncError::InvalidTransfer(
+                "cannot undo a transfer that was itself an undo".to_owned(),
+            ));
+        }
+
+        if repo.get_undo_of_transfer(transfer.id)?.is_some() {
+            return Err(SyncError::InvalidTransfer(
+                "transfer has already been undone".to_owned(),
+            ));
+        }
+
+        let max_age_days = self.ctx.config.sync.undo_max_age_days;
+        let max_age = chrono::Duration::try_days(max_age_days.into()).expect("days out of bounds");
+        if transfer.created_at < Utc::now() - max_age {
+            return Err(SyncError::InvalidTransfer(format!(
+                "cannot undo a transfer older than {} days",
+                max_age_days
+            )));
+        }
+
+        let tracks = repo.get_tracks_for_transfer(transfer.id)?;
+        if tracks.is_empty() {
+            return Err(SyncError::InvalidTransfer(
+                "transfer did not change any tracks".to_owned(),
+            ));
+        }
+
+        // Restore the source before touching the target so a failure can never lose a track
+        let num_restored = self.restore_removed_tracks(&tracks).await?;
+        self.remove_added_tracks(&tracks).await?;
+
+        Ok(num_restored)
+    }
+
+    /// Remove every track the transfer added, grouped by the playlist it was added to
+    async fn remove_added_tracks(&mut self, tracks: &[TransferTrack]) -> SyncResult<()> {
+        for (playlist, added) in
+            group_by_playlist(tracks, &[TrackAction::Added], |track| &track.playlist_to)
+        {
+            let ids = added.iter().map(|track| track.item_id.clone()).collect::<Vec<_>>();
+
+            match &playlist {
+                PlaylistType::Saved => {
+                    let ids = ids.iter().filter_map(ItemId::as_track).cloned().collect::<Vec<_>>();
+                    self.client.current_user_saved_tracks_remove_ids(&ids).await?;
+                }
+                PlaylistType::Id(id) => {
+                    self.client.playlist_remove_ids(id, &ids).await?;
+                }
+                PlaylistType::Template(_) => return Err(template_error()),
+            };
+
+            // The undo moves tracks in the opposite direction of the original transfer
+            for track in added {
+                self.track_log.push(TrackChange {
+                    item_id: track.item_id.clone(),
+                    action: TrackAction::Removed,
+                    playlist_from: track.playlist_to.clone(),
+                    playlist_to: track.playlist_from.clone(),
+                    position: track.position,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Put every track the transfer removed or evicted back where it was, skipping any that are already there
+    async fn restore_removed_tracks(&mut self, tracks: &[TransferTrack]) -> SyncResult<u32> {
+        let mut num_restored = 0;
+
+        for (playlist, mut removed) in group_by_playlist(
+            tracks,
+            &[TrackAction::Removed, TrackAction::Evicted],
+            |track| &track.playlist_from,
+        ) {
+            let existing = self.get_track_ids(&playlist).await?;
+            removed.retain(|track| !existing.contains(&track.item_id));
+            removed.sort_by_key(|track| track.position);
+
+            match &playlist {
+                // Saved tracks are ordered by when they were saved, so save the oldest (last) first
+                PlaylistType::Saved => {
+                    let ids = removed
+                        .iter()
+                        .rev()
+                        .filter_map(|track| track.item_id.as_track().cloned())
+                        .collect::<Vec<_>>();
+                    self.client.current_user_saved_tracks_add_ids(&ids).await?;
+                    num_restored += ids.len();
+                }
+
+                // Insert each run of consecutive tracks at its original position, in ascending order
+                PlaylistType::Id(id) => {
+                    for run in removed.chunk_by(|a, b| {
+                        a.position.zip(b.position).is_some_and(|(a, b)| a + 1 == b)
+                    }) {
+                        let ids = run.iter().map(|track| track.item_id.clone()).collect::<Vec<_>>();
+
+                        // The playlist may have shrunk since, in which case the run goes at the end
+                        let len = existing.len() + num_restored;
+                        let position = run[0].position.map(|position| {
+                            position
+                                .min(len.try_into().expect("size cant possibly be bigger than u32"))
+                        });
+
+                        self.client.playlist_insert_ids(id, &ids, position).await?;
+                        num_restored += run.len();
+                    }
+                }
+                PlaylistType::Template(_) => return Err(template_error()),
+            };
+
+            for track in removed {
+                self.track_log.push(TrackChange {
+                    item_id: track.item_id.clone(),
+                    action: TrackAction::Added,
+                    playlist_from: track.playlist_to.clone(),
+                    playlist_to: track.playlist_from.clone(),
+                    position: track.position,
+                });
+            }
+        }
+
+        Ok(num_restored.try_into().expect("size cant possibly be bigger than u32"))
+    }
+
+    /// Fetch the IDs currently in a playlist
+    async fn get_track_ids(&self, playlist: &PlaylistType) -> SyncResult<HashSet<ItemId>> {
+        let ids = match playlist {
+            PlaylistType::Saved => self.client.current_user_saved_item_ids().await?,
+            PlaylistType::Id(id) => self.client.playlist_item_ids(id).await?,
+            PlaylistType::Template(_) => return Err(template_error()),
+        };
+
+        Ok(ids.into_iter().collect())
+    }
+}
+
+/// Transfers are journalled with the playlist each template named at the time, so this is never expected
+fn template_error() -> SyncError {
+    SyncError::InvalidTransfer("cannot undo a transfer to a playlist template".to_owned())
+}
+
+/// Collect the tracks with any of some actions, grouped by a playlist and in the order they were recorded
+fn group_by_playlist<'a>(
+    tracks: &'a [TransferTrack],
+    actions: &[TrackAction],
+    playlist: impl Fn(&TransferTrack) -> &PlaylistType,
+) -> Vec<(PlaylistType, Vec<&'a TransferTrack>)> {
+    let mut groups: Vec<(PlaylistType, Vec<&TransferTrack>)> = vec![];
+
+    for track in tracks.iter().filter(|track| actions.contains(&track.action)) {
+        match groups.iter_mut().find(|(key, _)| key == playlist(track)) {
+            Some((_, group)) => group.push(track),
+            None => groups.push((playlist(track).clone(), vec![track])),
+        }
+    }
+
+    groups
+}
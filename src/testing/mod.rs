@@ -0,0 +1,84 @@
+use crate::{
+    api::{http::HttpClients, token::Token},
+    config,
+    context::AppContext,
+    db::{self, model::user::User, repo::user::UserRepo},
+};
+use std::collections::HashSet;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+pub mod spotify;
+
+/// Spotify user ID of the user created by `TestApp::start`
+pub const TEST_USER_ID: &str = "test_user";
+
+/// Application context wired up to a fake Spotify server and a throwaway database
+pub struct TestApp {
+    pub ctx: AppContext,
+    pub spotify: spotify::FakeSpotify,
+    pub user: User,
+    _dir: TempDir,
+}
+
+impl TestApp {
+    /// Start a fake Spotify server and create a user with a valid token
+    pub async fn start() -> Self {
+        let spotify = spotify::FakeSpotify::start(TEST_USER_ID).await;
+
+        let mut config = config::parse_default().expect("default config should parse");
+        config.spotify.api_base_url = spotify.api_base_url();
+        config.spotify.oauth2_token_url = spotify.token_url();
+        config.spotify.retry_max_wait_secs = 0; // Retry immediately to keep tests fast
+        config.spotify.max_requests_per_sec = 0;
+        config.web.public_url = "http://127.0.0.1".into();
+        config.web.jwt_secret = "test_secret".into();
+
+        let dir = tempfile::tempdir().expect("should create temp dir");
+        let db = db::init(&dir.path().join("modulate.db")).expect("database should initialize");
+        let http = HttpClients::new(&config.spotify).expect("http clients should build");
+        let ctx = AppContext { config, db, http };
+
+        let user = UserRepo::new(ctx.clone())
+            .upsert_user_token(&format!("spotify:user:{}", TEST_USER_ID), &valid_token())
+            .expect("user should be created");
+
+        Self {
+            ctx,
+            spotify,
+            user,
+            _dir: dir,
+        }
+    }
+
+    /// Serve the web app on a random local port, returning its URL
+    pub async fn serve_web(&self) -> String {
+        let app = crate::web::app(self.ctx.clone()).expect("app should build");
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("should bind to local port");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("should have address")
+        );
+
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service())
+                .await
+                .expect("web server should run");
+        });
+
+        url
+    }
+}
+
+/// A token that will not need to be refreshed for the duration of a test
+pub fn valid_token() -> Token {
+    let expires_in = chrono::Duration::try_hours(1).expect("won't overflow");
+
+    Token {
+        access_token: spotify::FAKE_ACCESS_TOKEN.into(),
+        expires_in,
+        expires_at: chrono::Utc::now() + expires_in,
+        refresh_token: Some("fake-refresh-token".into()),
+        scopes: HashSet::new(),
+    }
+}
@@ -0,0 +1,746 @@
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{OriginalUri, Path, Query, Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Instant,
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+/// Access token handed out by the fake token endpoint
+pub const FAKE_ACCESS_TOKEN: &str = "fake-access-token";
+
+/// Path of the fake API, relative to the server URL
+const API_PREFIX: &str = "/v1";
+
+/// In-process fake of the parts of the Spotify Web API that `api::client::Client` uses
+pub struct FakeSpotify {
+    pub url: String,
+    state: Arc<Mutex<FakeState>>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Debug, Default)]
+pub struct FakeState {
+    pub user_id: String,
+    pub playlists: BTreeMap<String, FakePlaylist>,
+    pub saved_tracks: Vec<FakeTrack>,
+    /// Track metadata that overrides the defaults generated from each ID
+    pub catalog: HashMap<String, Value>,
+    pub requests: Vec<FakeRequest>,
+    failures: VecDeque<ScriptedFailure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakePlaylist {
+    pub name: String,
+    pub owner_id: String,
+    pub collaborative: bool,
+    pub snapshot: u32,
+    pub tracks: Vec<FakeTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeTrack {
+    /// A bare ID for tracks, or the full URI for episodes and local files
+    pub id: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A request the fake server received, and when
+#[derive(Debug, Clone)]
+pub struct FakeRequest {
+    /// Method and path, e.g. `GET /me`
+    pub endpoint: String,
+    pub received_at: Instant,
+    /// `None` while the request is still being handled
+    pub responded_at: Option<Instant>,
+}
+
+/// A failure to return instead of the real response for a matching request
+#[derive(Debug, Clone)]
+pub enum Failure {
+    TooManyRequests {
+        retry_after: Option<u64>,
+    },
+    Status(StatusCode),
+    EmptyBody(StatusCode),
+    Delay(std::time::Duration),
+    /// Make the change, then respond with an error as if the response was lost on the way back
+    AfterWrite(StatusCode),
+}
+
+#[derive(Debug, Clone)]
+struct ScriptedFailure {
+    method: Method,
+    path: String,
+    failure: Failure,
+    remaining: u32,
+}
+
+impl FakeSpotify {
+    /// Start the fake server on a random local port
+    pub async fn start(user_id: &str) -> Self {
+        let state = Arc::new(Mutex::new(FakeState {
+            user_id: user_id.to_owned(),
+            ..Default::default()
+        }));
+
+        let api = Router::new()
+            .route("/me", get(get_me))
+            .route("/me/playlists", get(get_my_playlists))
+            .route(
+                "/me/tracks",
+                get(get_saved_tracks).put(save_tracks).delete(delete_saved_tracks),
+            )
+            .route("/tracks", get(get_tracks))
+            .route("/episodes", get(get_episodes))
+            .route("/users/{user_id}/playlists", post(create_playlist))
+            .route("/playlists/{id}", get(get_playlist).put(update_playlist))
+            .route(
+                "/playlists/{id}/tracks",
+                get(get_playlist_tracks)
+                    .post(add_playlist_tracks)
+                    .delete(remove_playlist_tracks),
+            );
+
+        let app = Router::new()
+            .nest(API_PREFIX, api)
+            .route("/api/token", post(exchange_token))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                record_and_fail,
+            ))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("should bind to local port");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("should have address")
+        );
+
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("fake server should run");
+        });
+
+        Self { url, state, handle }
+    }
+
+    /// Base URL to use for `spotify.api_base_url`
+    pub fn api_base_url(&self) -> String {
+        format!("{}{}", self.url, API_PREFIX)
+    }
+
+    /// URL to use for `spotify.oauth2_token_url`
+    pub fn token_url(&self) -> String {
+        format!("{}/api/token", self.url)
+    }
+
+    /// Lock the in-memory state to inspect or modify it
+    pub fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().expect("fake state should not be poisoned")
+    }
+
+    /// Create a playlist owned by the given user, returning its ID
+    pub fn add_playlist(&self, id: &str, owner_id: &str, track_ids: &[&str]) -> String {
+        self.state().playlists.insert(
+            id.to_owned(),
+            FakePlaylist {
+                name: format!("Playlist {}", id),
+                owner_id: owner_id.to_owned(),
+                collaborative: false,
+                snapshot: 1,
+                tracks: added_in_order(track_ids),
+            },
+        );
+
+        id.to_owned()
+    }
+
+    /// Find the ID of a playlist by its name
+    pub fn playlist_named(&self, name: &str) -> Option<String> {
+        self.state()
+            .playlists
+            .iter()
+            .find(|(_, playlist)| playlist.name == name)
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Change a playlist's tracks as the user would in Spotify, which gives it a new snapshot
+    pub fn edit_playlist(&self, id: &str, edit: impl FnOnce(&mut Vec<FakeTrack>)) {
+        let mut state = self.state();
+        let playlist = state.playlists.get_mut(id).expect("playlist exists");
+        edit(&mut playlist.tracks);
+        playlist.snapshot += 1;
+    }
+
+    /// Replace the user's saved tracks, newest first (as Spotify returns them)
+    pub fn set_saved_tracks(&self, track_ids: &[&str]) {
+        let oldest_first = track_ids.iter().rev().copied().collect::<Vec<_>>();
+        self.state().saved_tracks = added_in_order(&oldest_first).into_iter().rev().collect();
+    }
+
+    /// IDs of the tracks in a playlist, in order
+    pub fn playlist_track_ids(&self, id: &str) -> Vec<String> {
+        self.state()
+            .playlists
+            .get(id)
+            .map(|playlist| playlist.tracks.iter().map(|track| track.id.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// IDs of the user's saved tracks, in order
+    pub fn saved_track_ids(&self) -> Vec<String> {
+        self.state().saved_tracks.iter().map(|track| track.id.clone()).collect()
+    }
+
+    /// Fail the next `times` requests matching the method and path (relative to the API root, e.g. `/me/tracks`)
+    pub fn fail(&self, method: Method, path: &str, failure: Failure, times: u32) {
+        self.state().failures.push_back(ScriptedFailure {
+            method,
+            path: path.to_owned(),
+            failure,
+            remaining: times,
+        });
+    }
+
+    /// Override some of a track's metadata, e.g. `json!({ "explicit": true })`
+    pub fn set_track(&self, id: &str, metadata: Value) {
+        self.state().catalog.insert(id.to_owned(), metadata);
+    }
+
+    /// Number of requests received that match the method and path
+    pub fn request_count(&self, method: &Method, path: &str) -> usize {
+        self.requests(method, path).len()
+    }
+
+    /// Wait until a request matching the method and path has been received, however long it takes to
+    /// respond to it
+    pub async fn wait_for_request(&self, method: &Method, path: &str) {
+        let wait = async {
+            while self.request_count(method, path) == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        };
+
+        tokio::time::timeout(std::time::Duration::from_secs(30), wait)
+            .await
+            .expect("request should have been received");
+    }
+
+    /// Every request received that matches the method and path, in the order they were received
+    pub fn requests(&self, method: &Method, path: &str) -> Vec<FakeRequest> {
+        let endpoint = format!("{} {}", method, path);
+        self.state()
+            .requests
+            .iter()
+            .filter(|req| req.endpoint == endpoint)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for FakeSpotify {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl FakeTrack {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            added_at: Utc::now(),
+        }
+    }
+}
+
+/// Tracks added a second apart, the last most recently
+fn added_in_order(track_ids: &[&str]) -> Vec<FakeTrack> {
+    let now = Utc::now();
+
+    track_ids
+        .iter()
+        .enumerate()
+        .map(|(index, id)| FakeTrack {
+            id: (*id).to_owned(),
+            added_at: now - chrono::Duration::seconds((track_ids.len() - index) as i64),
+        })
+        .collect()
+}
+
+type SharedState = State<Arc<Mutex<FakeState>>>;
+
+/// Record every request and return a scripted failure if one matches
+async fn record_and_fail(State(state): SharedState, req: Request<Body>, next: Next) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().strip_prefix(API_PREFIX).unwrap_or(req.uri().path()).to_owned();
+
+    let (request, failure) = {
+        let mut state = state.lock().unwrap();
+        let request = state.requests.len();
+        state.requests.push(FakeRequest {
+            endpoint: format!("{} {}", method, path),
+            received_at: Instant::now(),
+            responded_at: None,
+        });
+
+        let scripted = state
+            .failures
+            .iter()
+            .position(|failure| failure.method == method && failure.path == path);
+
+        let failure = scripted.map(|index| {
+            let scripted = &mut state.failures[index];
+            scripted.remaining -= 1;
+            let failure = scripted.failure.clone();
+
+            if scripted.remaining == 0 {
+                state.failures.remove(index);
+            }
+
+            failure
+        });
+
+        (request, failure)
+    };
+
+    let res = match failure {
+        Some(Failure::TooManyRequests { retry_after }) => {
+            let mut res = StatusCode::TOO_MANY_REQUESTS.into_response();
+            if let Some(secs) = retry_after {
+                res.headers_mut().insert(header::RETRY_AFTER, secs.into());
+            }
+            res
+        }
+        Some(Failure::Status(status)) => error(status, "scripted failure"),
+        Some(Failure::EmptyBody(status)) => status.into_response(),
+        Some(Failure::Delay(duration)) => {
+            tokio::time::sleep(duration).await;
+            next.run(req).await
+        }
+        Some(Failure::AfterWrite(status)) => {
+            next.run(req).await;
+            error(status, "scripted failure")
+        }
+        None => next.run(req).await,
+    };
+
+    state.lock().unwrap().requests[request].responded_at = Some(Instant::now());
+    res
+}
+
+/// Respond with an error body in the same shape Spotify uses
+fn error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({ "error": { "status": status.as_u16(), "message": message } })),
+    )
+        .into_response()
+}
+
+fn not_found() -> Response {
+    error(StatusCode::NOT_FOUND, "Resource not found")
+}
+
+#[derive(Debug, Deserialize)]
+struct PageParams {
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Build a paging object the same way Spotify does, including the `next` URL
+fn paginate(url: &str, params: &PageParams, items: Vec<Value>) -> Value {
+    let limit = params.limit.unwrap_or(20);
+    let offset = params.offset.unwrap_or(0);
+    let total = items.len();
+
+    let page_url = |offset: usize| format!("{}?offset={}&limit={}", url, offset, limit);
+
+    json!({
+        "href": page_url(offset),
+        "limit": limit,
+        "offset": offset,
+        "total": total,
+        "next": (offset + limit < total).then(|| page_url(offset + limit)),
+        "previous": (offset > 0).then(|| page_url(offset.saturating_sub(limit))),
+        "items": items.into_iter().skip(offset).take(limit).collect::<Vec<_>>(),
+    })
+}
+
+/// Rebuild the absolute URL of a request (without query params) to use for paging links
+fn request_url(uri: &Uri, headers: &HeaderMap) -> String {
+    let host = headers.get(header::HOST).and_then(|host| host.to_str().ok());
+    format!("http://{}{}", host.unwrap_or_default(), uri.path())
+}
+
+fn playlist_json(id: &str, playlist: &FakePlaylist) -> Value {
+    json!({
+        "id": id,
+        "name": playlist.name,
+        "snapshot_id": format!("snapshot-{}", playlist.snapshot),
+        "images": [],
+        "owner": { "id": playlist.owner_id },
+        "external_urls": { "spotify": format!("https://open.spotify.com/playlist/{}", id) },
+    })
+}
+
+fn track_item_json(state: &FakeState, track: &FakeTrack) -> Value {
+    let item = if let Some(id) = track.id.strip_prefix("spotify:episode:") {
+        episode_json(id)
+    } else if track.id.starts_with("spotify:local:") {
+        local_json(&track.id)
+    } else {
+        track_json(state, &track.id)
+    };
+
+    json!({
+        "added_at": track.added_at.to_rfc3339(),
+        "is_local": track.id.starts_with("spotify:local:"),
+        "track": item,
+    })
+}
+
+/// Build an episode object, named after its ID
+fn episode_json(id: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "episode",
+        "uri": format!("spotify:episode:{}", id),
+        "name": format!("Episode {}", id),
+        "explicit": false,
+        "duration_ms": 1_800_000,
+        "show": { "name": "Fake Show" },
+    })
+}
+
+/// Build a local file object, which has no ID and only the metadata from the user's device
+fn local_json(uri: &str) -> Value {
+    json!({
+        "id": null,
+        "type": "track",
+        "uri": uri,
+        "name": "Local File",
+        "explicit": false,
+        "duration_ms": 180_000,
+        "artists": [{ "id": null, "name": "Local Artist" }],
+        "album": { "name": "Local Album", "release_date": null },
+    })
+}
+
+/// Build a full track object, named after its ID unless overridden in the catalog
+fn track_json(state: &FakeState, id: &str) -> Value {
+    let mut track = json!({
+        "id": id,
+        "type": "track",
+        "uri": format!("spotify:track:{}", id),
+        "name": format!("Track {}", id),
+        "explicit": false,
+        "duration_ms": 180_000,
+        "artists": [{ "id": "FakeArtist", "name": "Fake Artist" }],
+        "album": { "name": "Fake Album", "release_date": "2020-01-01" },
+    });
+
+    if let (Some(track), Some(Value::Object(metadata))) =
+        (track.as_object_mut(), state.catalog.get(id))
+    {
+        track.extend(metadata.clone());
+    }
+
+    track
+}
+
+/// Strip a `spotify:track:` prefix from a URI, keeping other kinds of item whole
+fn id_from_uri(uri: &str) -> String {
+    match uri.strip_prefix("spotify:track:") {
+        Some(id) => id.to_owned(),
+        None => uri.to_owned(),
+    }
+}
+
+async fn get_me(State(state): SharedState) -> Response {
+    let state = state.lock().unwrap();
+
+    Json(json!({
+        "id": state.user_id,
+        "display_name": format!("User {}", state.user_id),
+        "images": [],
+        "external_urls": { "spotify": format!("https://open.spotify.com/user/{}", state.user_id) },
+    }))
+    .into_response()
+}
+
+async fn get_my_playlists(
+    State(state): SharedState,
+    Query(params): Query<PageParams>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    let state = state.lock().unwrap();
+    let items = state
+        .playlists
+        .iter()
+        .map(|(id, playlist)| playlist_json(id, playlist))
+        .collect();
+
+    Json(paginate(&request_url(&uri, &headers), &params, items)).into_response()
+}
+
+async fn get_saved_tracks(
+    State(state): SharedState,
+    Query(params): Query<PageParams>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    let state = state.lock().unwrap();
+    let items = state.saved_tracks.iter().map(|track| track_item_json(&state, track)).collect();
+
+    Json(paginate(&request_url(&uri, &headers), &params, items)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct IdsBody {
+    ids: Vec<String>,
+}
+
+async fn save_tracks(State(state): SharedState, Json(body): Json<IdsBody>) -> Response {
+    if body.ids.len() > 50 {
+        return error(StatusCode::BAD_REQUEST, "Too many ids requested");
+    }
+
+    let mut state = state.lock().unwrap();
+
+    // Newly saved tracks appear at the top of the library, and saving a track twice is a no-op
+    for id in &body.ids {
+        if !state.saved_tracks.iter().any(|track| &track.id == id) {
+            state.saved_tracks.insert(0, FakeTrack::new(id));
+        }
+    }
+
+    StatusCode::OK.into_response()
+}
+
+async fn delete_saved_tracks(State(state): SharedState, Json(body): Json<IdsBody>) -> Response {
+    if body.ids.len() > 50 {
+        return error(StatusCode::BAD_REQUEST, "Too many ids requested");
+    }
+
+    let mut state = state.lock().unwrap();
+    state.saved_tracks.retain(|track| !body.ids.contains(&track.id));
+
+    // Spotify responds to library changes with an empty body
+    StatusCode::OK.into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct TracksParams {
+    ids: String,
+}
+
+async fn get_tracks(State(state): SharedState, Query(params): Query<TracksParams>) -> Response {
+    let ids = params.ids.split(',').collect::<Vec<_>>();
+    if ids.len() > 50 {
+        return error(StatusCode::BAD_REQUEST, "Too many ids requested");
+    }
+
+    // Every track exists
+    let state = state.lock().unwrap();
+    let tracks = ids.iter().map(|id| track_json(&state, id)).collect::<Vec<_>>();
+
+    Json(json!({ "tracks": tracks })).into_response()
+}
+
+async fn get_episodes(Query(params): Query<TracksParams>) -> Response {
+    let ids = params.ids.split(',').collect::<Vec<_>>();
+    if ids.len() > 50 {
+        return error(StatusCode::BAD_REQUEST, "Too many ids requested");
+    }
+
+    // Every episode exists
+    let episodes = ids.iter().map(|id| episode_json(id)).collect::<Vec<_>>();
+
+    Json(json!({ "episodes": episodes })).into_response()
+}
+
+async fn get_playlist(State(state): SharedState, Path(id): Path<String>) -> Response {
+    let state = state.lock().unwrap();
+
+    match state.playlists.get(&id) {
+        Some(playlist) => Json(playlist_json(&id, playlist)).into_response(),
+        None => not_found(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatePlaylistBody {
+    name: String,
+}
+
+async fn create_playlist(
+    State(state): SharedState,
+    Path(user_id): Path<String>,
+    Json(body): Json<CreatePlaylistBody>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if user_id != state.user_id {
+        return error(
+            StatusCode::FORBIDDEN,
+            "You cannot create playlists for another user",
+        );
+    }
+
+    // IDs are 22 characters like real ones, so they can be parsed back from a URI
+    let id = format!("CreatedPlaylist{:07}", state.playlists.len());
+    let playlist = FakePlaylist {
+        name: body.name,
+        owner_id: user_id,
+        collaborative: false,
+        snapshot: 1,
+        tracks: vec![],
+    };
+    let json = playlist_json(&id, &playlist);
+    state.playlists.insert(id, playlist);
+
+    (StatusCode::CREATED, Json(json)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatePlaylistBody {
+    name: String,
+}
+
+async fn update_playlist(
+    State(state): SharedState,
+    Path(id): Path<String>,
+    Json(body): Json<UpdatePlaylistBody>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    let user_id = state.user_id.clone();
+
+    let Some(playlist) = state.playlists.get_mut(&id) else {
+        return not_found();
+    };
+
+    if playlist.owner_id != user_id && !playlist.collaborative {
+        return error(StatusCode::FORBIDDEN, "You cannot edit this playlist");
+    }
+
+    playlist.name = body.name;
+
+    StatusCode::OK.into_response()
+}
+
+async fn get_playlist_tracks(
+    State(state): SharedState,
+    Path(id): Path<String>,
+    Query(params): Query<PageParams>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    let state = state.lock().unwrap();
+
+    let Some(playlist) = state.playlists.get(&id) else {
+        return not_found();
+    };
+
+    let items = playlist.tracks.iter().map(|track| track_item_json(&state, track)).collect();
+
+    Json(paginate(&request_url(&uri, &headers), &params, items)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct AddTracksBody {
+    uris: Vec<String>,
+    position: Option<usize>,
+}
+
+async fn add_playlist_tracks(
+    State(state): SharedState,
+    Path(id): Path<String>,
+    Json(body): Json<AddTracksBody>,
+) -> Response {
+    if body.uris.len() > 100 {
+        return error(StatusCode::BAD_REQUEST, "Too many uris requested");
+    }
+
+    let mut state = state.lock().unwrap();
+
+    let Some(playlist) = state.playlists.get_mut(&id) else {
+        return not_found();
+    };
+
+    if body.uris.iter().any(|uri| uri.starts_with("spotify:local:")) {
+        return error(StatusCode::BAD_REQUEST, "Local tracks cannot be added");
+    }
+
+    let position = body.position.unwrap_or(playlist.tracks.len());
+    if position > playlist.tracks.len() {
+        return error(StatusCode::BAD_REQUEST, "Index out of bounds");
+    }
+
+    playlist.tracks.splice(
+        position..position,
+        body.uris.iter().map(|uri| FakeTrack::new(&id_from_uri(uri))),
+    );
+    playlist.snapshot += 1;
+
+    (
+        StatusCode::CREATED,
+        Json(json!({ "snapshot_id": format!("snapshot-{}", playlist.snapshot) })),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoveTracksBody {
+    tracks: Vec<UriObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UriObject {
+    uri: String,
+}
+
+async fn remove_playlist_tracks(
+    State(state): SharedState,
+    Path(id): Path<String>,
+    Json(body): Json<RemoveTracksBody>,
+) -> Response {
+    if body.tracks.len() > 100 {
+        return error(StatusCode::BAD_REQUEST, "Too many tracks requested");
+    }
+
+    let mut state = state.lock().unwrap();
+
+    let Some(playlist) = state.playlists.get_mut(&id) else {
+        return not_found();
+    };
+
+    let ids = body.tracks.iter().map(|track| id_from_uri(&track.uri)).collect::<Vec<_>>();
+    playlist.tracks.retain(|track| !ids.contains(&track.id));
+    playlist.snapshot += 1;
+
+    Json(json!({ "snapshot_id": format!("snapshot-{}", playlist.snapshot) })).into_response()
+}
+
+async fn exchange_token(axum::Form(_params): axum::Form<HashMap<String, String>>) -> Response {
+    Json(json!({
+        "access_token": FAKE_ACCESS_TOKEN,
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "refresh_token": "fake-refresh-token",
+        "scope": "user-library-read user-library-modify playlist-read-private playlist-modify-public playlist-modify-private",
+    }))
+    .into_response()
+}
@@ -1,6 +1,9 @@
 use self::error::WebResult;
-use crate::context::AppContext;
-use axum::http::{HeaderValue, Method, header};
+use crate::{context::AppContext, shutdown::Shutdown};
+use axum::{
+    Router,
+    http::{HeaderValue, Method, header},
+};
 use tokio::net::TcpListener;
 use tower_cookies::CookieManagerLayer;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
@@ -13,7 +16,8 @@ mod session;
 mod util;
 mod view;
 
-pub async fn serve(ctx: AppContext) -> WebResult<()> {
+/// Serve the web app until shutdown is requested, finishing any requests already in progress
+pub async fn serve(ctx: AppContext, mut shutdown: Shutdown) -> WebResult<()> {
     tracing::info!(
         "Starting web server on {}:{} (Spotify redirect URI: {}/callback)",
         ctx.config.web.host,
@@ -21,24 +25,28 @@ pub async fn serve(ctx: AppContext) -> WebResult<()> {
         ctx.config.web.public_url
     );
 
+    axum::serve(
+        TcpListener::bind(format!("{}:{}", ctx.config.web.host, ctx.config.web.port)).await?,
+        app(ctx)?.into_make_service(),
+    )
+    .with_graceful_shutdown(async move { shutdown.requested().await })
+    .await?;
+
+    Ok(())
+}
+
+/// Build the application router along with all middleware layers
+pub fn app(ctx: AppContext) -> WebResult<Router> {
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
         .allow_headers([header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
         .allow_origin(ctx.config.web.public_url.parse::<HeaderValue>()?)
         .allow_credentials(true);
 
-    let app = crate::web::router::router(ctx.clone())
+    Ok(crate::web::router::router(ctx)
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .layer(CookieManagerLayer::new())
         .layer(sentry::integrations::tower::NewSentryLayer::new_from_top())
-        .layer(sentry::integrations::tower::SentryHttpLayer::new().enable_transaction());
-
-    axum::serve(
-        TcpListener::bind(format!("{}:{}", ctx.config.web.host, ctx.config.web.port)).await?,
-        app.into_make_service(),
-    )
-    .await?;
-
-    Ok(())
+        .layer(sentry::integrations::tower::SentryHttpLayer::new().enable_transaction()))
 }
@@ -49,6 +49,13 @@ fn maybe_get_response(error: &WebError) -> Option<(StatusCode, Value)> {
         WebError::UnauthorizedError | WebError::JwtExpiredError | WebError::JwtInvalidError => {
             (StatusCode::UNAUTHORIZED, Value::String(error.to_string()))
         }
+        WebError::SyncError(crate::sync::error::SyncError::InvalidTransfer(err)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, Value::String(err.clone()))
+        }
+        WebError::DbError(crate::db::error::DbError::InvalidPlaylistTemplate(_)) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Value::String(error.to_string()),
+        ),
         WebError::InvalidFormData(err) => {
             (StatusCode::UNPROCESSABLE_ENTITY, Value::String(err.clone()))
         }
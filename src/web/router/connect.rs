@@ -44,7 +44,7 @@ async fn handle_callback(
     State(ctx): State<AppContext>,
 ) -> WebResult<impl IntoResponse> {
     // Ensure the state we get back from the API key is the value we set before the user was redirected
-    if !cookies.get(CSRF_COOKIE).is_some_and(|cookie| cookie.value() == params.state) {
+    if cookies.get(CSRF_COOKIE).is_none_or(|cookie| cookie.value() != params.state) {
         return Err(WebError::CsrfInvalidError);
     }
 
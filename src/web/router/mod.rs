@@ -17,6 +17,10 @@ use tower_cookies::{
 };
 
 mod connect;
+#[cfg(test)]
+mod testing;
+mod track;
+mod transfer;
 mod user;
 mod watcher;
 
@@ -31,6 +35,8 @@ pub fn router(ctx: AppContext) -> Router {
         .with_state(ctx.clone())
         .merge(connect::router(ctx.clone()))
         .merge(watcher::router(ctx.clone()))
+        .merge(track::router(ctx.clone()))
+        .merge(transfer::router(ctx.clone()))
         .merge(user::router(ctx))
 }
 
@@ -0,0 +1,73 @@
+use super::JWT_COOKIE;
+use crate::{testing::TestApp, web::util::jwt};
+use reqwest::{Method, StatusCode, header};
+use serde_json::{Value, json};
+
+/// A served web app with an authenticated HTTP client for the test user
+pub struct TestServer {
+    pub app: TestApp,
+    pub url: String,
+    pub http: reqwest::Client,
+    pub cookie: String,
+}
+
+impl TestServer {
+    /// Start a test app, serve the web app and sign in as the test user
+    pub async fn start() -> Self {
+        let app = TestApp::start().await;
+        let url = app.serve_web().await;
+        let jwt = jwt::sign_jwt(&app.ctx.config.web.jwt_secret, &app.user.user_uri).unwrap();
+
+        Self {
+            app,
+            url,
+            http: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap(),
+            cookie: format!("{}={}", JWT_COOKIE, jwt),
+        }
+    }
+
+    /// Send an authenticated request, returning the status and JSON body
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut req = self
+            .http
+            .request(method, format!("{}{}", self.url, path))
+            .header(header::COOKIE, &self.cookie);
+
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+
+        let res = req.send().await.unwrap();
+        let status = res.status();
+
+        (status, res.json().await.unwrap_or(Value::Null))
+    }
+
+    /// Create a watcher through the web API with an hourly interval
+    pub async fn create_watcher(
+        &self,
+        from: &str,
+        to: &str,
+        should_remove: bool,
+    ) -> (StatusCode, Value) {
+        self.request(
+            Method::POST,
+            "/watchers",
+            Some(json!({
+                "playlist_from": from,
+                "playlist_to": to,
+                "should_remove": should_remove,
+                "sync_interval": "hour",
+            })),
+        )
+        .await
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    api::id::ItemId,
+    context::AppContext,
+    db::repo::transfer::TransferRepo,
+    web::{
+        error::{WebError, WebResult},
+        middleware::auth,
+        session,
+    },
+};
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, State},
+    middleware,
+    response::IntoResponse,
+    routing::get,
+};
+use serde::Deserialize;
+use serde_json::json;
+use validator::Validate;
+
+const DEFAULT_HISTORY_PER_PAGE: u32 = 50;
+
+pub fn router(ctx: AppContext) -> Router {
+    Router::new()
+        .route("/tracks/{id}/transfers", get(get_track_transfers))
+        .route_layer(middleware::from_fn_with_state(
+            ctx.clone(),
+            auth::middleware,
+        ))
+        .with_state(ctx)
+}
+
+#[derive(Deserialize)]
+struct TrackParams {
+    id: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct TrackHistoryParams {
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1))]
+    page: u32,
+    #[serde(default = "default_per_page")]
+    #[validate(range(min = 1, max = 100))]
+    per_page: u32,
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    DEFAULT_HISTORY_PER_PAGE
+}
+
+/// Search the user's transfer history for every change made to a track or episode
+async fn get_track_transfers(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<TrackParams>,
+    Query(query): Query<TrackHistoryParams>,
+) -> WebResult<impl IntoResponse> {
+    query.validate()?;
+
+    let item_id = ItemId::parse_from_input(&params.id)
+        .map_err(|_| WebError::InvalidFormData("Invalid track ID.".into()))?;
+
+    let history = TransferRepo::new(ctx).get_track_history_for_user(
+        &session.user.user_uri,
+        &item_id,
+        query.per_page,
+        (query.page - 1).saturating_mul(query.per_page),
+    )?;
+
+    Ok(Json(json!({
+        "success": true,
+        "item_id": item_id,
+        "history": history,
+        "page": query.page,
+        "per_page": query.per_page,
+    })))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::repo::watcher::WatcherRepo, testing::TEST_USER_ID, web::router::testing::TestServer,
+    };
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    const SOURCE: &str = "SourcePlaylist00000000";
+    const TARGET: &str = "TargetPlaylist00000000";
+
+    #[tokio::test]
+    async fn it_searches_track_history_for_the_current_user() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &["b"]);
+        server
+            .create_watcher(
+                &format!("spotify:playlist:{}", SOURCE),
+                &format!("spotify:playlist:{}", TARGET),
+                true,
+            )
+            .await;
+
+        let id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+
+        let (status, body) =
+            server.request(Method::GET, "/tracks/spotify:track:a/transfers", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["item_id"], json!({"type": "track", "id": "a"}));
+        let history = body["history"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["action"], "removed");
+        assert_eq!(history[0]["position"], 0);
+        assert_eq!(history[1]["action"], "added");
+        assert_eq!(history[1]["position"], 1);
+
+        let (_, body) = server.request(Method::GET, "/tracks/b/transfers", None).await;
+        let actions = body["history"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| change["action"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(actions, vec!["removed", "skipped_duplicate"]);
+
+        let (_, body) = server.request(Method::GET, "/tracks/c/transfers", None).await;
+        assert!(body["history"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    context::AppContext,
+    db::repo::{transfer::TransferRepo, watcher::WatcherRepo},
+    web::{
+        error::{WebError, WebResult},
+        middleware::auth,
+        session,
+    },
+};
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    middleware,
+    response::IntoResponse,
+    routing::post,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+pub fn router(ctx: AppContext) -> Router {
+    Router::new()
+        .route("/transfers/{id}/undo", post(undo_transfer))
+        .route_layer(middleware::from_fn_with_state(
+            ctx.clone(),
+            auth::middleware,
+        ))
+        .with_state(ctx)
+}
+
+#[derive(Deserialize)]
+struct ManageTransferParams {
+    id: u32,
+}
+
+async fn undo_transfer(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageTransferParams>,
+) -> WebResult<impl IntoResponse> {
+    let transfer = match TransferRepo::new(ctx.clone()).get_transfer_by_id(params.id)? {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    // Only the owner of the watcher may undo its transfers
+    if WatcherRepo::new(ctx.clone())
+        .get_watcher_by_id_and_user(transfer.watcher_id, &session.user.user_uri)?
+        .is_none()
+    {
+        return Err(WebError::NotFoundError);
+    }
+
+    let count = crate::sync::undo_transfer(ctx, session.client, &transfer).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "num_tracks_restored": count
+    })))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        api::id::PlaylistId,
+        db::{
+            model::{playlist::PlaylistType, watcher::NewWatcher},
+            repo::{transfer::TransferRepo, watcher::WatcherRepo},
+        },
+        testing::TEST_USER_ID,
+        web::router::testing::TestServer,
+    };
+    use reqwest::{Method, StatusCode};
+
+    const SOURCE: &str = "SourcePlaylist00000000";
+    const TARGET: &str = "TargetPlaylist00000000";
+
+    fn uri(id: &str) -> String {
+        format!("spotify:playlist:{}", id)
+    }
+
+    /// Create a watcher, sync it once and return the ID of the resulting transfer
+    async fn sync(server: &TestServer, from: &str, to: &str) -> u32 {
+        server.create_watcher(from, to, true).await;
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+        server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+
+        TransferRepo::new(server.app.ctx.clone())
+            .get_transfers_for_watcher(id, 1, 0)
+            .unwrap()[0]
+            .id
+    }
+
+    #[tokio::test]
+    async fn it_undoes_a_transfer_between_playlists() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b", "c"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x", "b"]);
+        let id = sync(&server, &uri(SOURCE), &uri(TARGET)).await;
+
+        let (status, body) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["num_tracks_restored"], 3);
+        assert_eq!(
+            server.app.spotify.playlist_track_ids(SOURCE),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            server.app.spotify.playlist_track_ids(TARGET),
+            vec!["x", "b"]
+        );
+
+        // The undo is recorded as its own transfer and cannot be repeated
+        let undo = TransferRepo::new(server.app.ctx.clone()).get_undo_of_transfer(id).unwrap();
+        assert!(undo.is_some_and(|undo| undo.num_tracks_transferred == 3));
+
+        let (status, _) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_puts_evicted_tracks_back_when_undone() {
+        const OVERFLOW: &str = "OverflowPlaylist000000";
+
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &["x", "y"]);
+        server.app.spotify.add_playlist(OVERFLOW, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+
+        let watcher_id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/options", watcher_id),
+                Some(serde_json::json!({ "max_tracks": 2, "overflow_playlist": OVERFLOW })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        server
+            .request(
+                Method::POST,
+                &format!("/watchers/{}/sync", watcher_id),
+                None,
+            )
+            .await;
+
+        assert_eq!(
+            server.app.spotify.playlist_track_ids(TARGET),
+            vec!["y", "a"]
+        );
+        assert_eq!(server.app.spotify.playlist_track_ids(OVERFLOW), vec!["x"]);
+
+        let id = TransferRepo::new(server.app.ctx.clone())
+            .get_transfers_for_watcher(watcher_id, 1, 0)
+            .unwrap()[0]
+            .id;
+        let (status, _) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(server.app.spotify.playlist_track_ids(SOURCE), vec!["a"]);
+        assert_eq!(
+            server.app.spotify.playlist_track_ids(TARGET),
+            vec!["x", "y"]
+        );
+        assert!(server.app.spotify.playlist_track_ids(OVERFLOW).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_restores_liked_tracks_through_the_library() {
+        let server = TestServer::start().await;
+        server.app.spotify.set_saved_tracks(&["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let id = sync(&server, "_liked", &uri(TARGET)).await;
+        assert!(server.app.spotify.saved_track_ids().is_empty());
+
+        let (status, _) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(server.app.spotify.saved_track_ids(), vec!["a", "b"]);
+        assert!(server.app.spotify.playlist_track_ids(TARGET).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_undo_old_transfers() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let id = sync(&server, &uri(SOURCE), &uri(TARGET)).await;
+
+        server
+            .app
+            .ctx
+            .db
+            .get()
+            .unwrap()
+            .execute(
+                "UPDATE transfers SET created_at = ?1 WHERE id = ?2",
+                (
+                    (chrono::Utc::now() - chrono::Duration::days(8)).to_rfc3339(),
+                    id,
+                ),
+            )
+            .unwrap();
+
+        let (status, _) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(server.app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_hides_other_users_transfers() {
+        let server = TestServer::start().await;
+        let ctx = server.app.ctx.clone();
+        let watcher_repo = WatcherRepo::new(ctx.clone());
+        watcher_repo
+            .create_watcher(&NewWatcher {
+                should_remove: true,
+                ..NewWatcher::new(
+                    "spotify:user:someone_else",
+                    PlaylistType::Saved,
+                    PlaylistType::Id(PlaylistId(TARGET.into())),
+                )
+            })
+            .unwrap();
+        let watcher =
+            watcher_repo.get_watchers_by_user("spotify:user:someone_else").unwrap()[0].clone();
+        let transfer_repo = TransferRepo::new(ctx);
+        transfer_repo
+            .log_transfer(watcher.id, &1, &None, chrono::Utc::now(), &[], None)
+            .unwrap();
+        let id = transfer_repo.get_transfers_for_watcher(watcher.id, 1, 0).unwrap()[0].id;
+
+        let (status, _) =
+            server.request(Method::POST, &format!("/transfers/{}/undo", id), None).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
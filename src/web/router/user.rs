@@ -2,7 +2,7 @@ use super::JWT_COOKIE;
 use crate::{
     api,
     context::AppContext,
-    db::repo::{user::UserRepo, watcher::WatcherRepo},
+    db::repo::{transfer::TransferRepo, user::UserRepo, watcher::WatcherRepo},
     web::util::cookie::unset_cookie,
     web::{error::WebResult, middleware::auth, session, view::DashboardTemplate},
 };
@@ -15,9 +15,12 @@ use axum::{
     routing::{delete, get},
 };
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use tower_cookies::Cookies;
 
+/// Number of recent transfers to show for each watcher on the dashboard
+const DASHBOARD_TRANSFER_LIMIT: u32 = 5;
+
 pub fn router(ctx: AppContext) -> Router {
     Router::new()
         .route("/me", get(get_current_user_dashboard))
@@ -37,6 +40,16 @@ async fn get_current_user_dashboard(
 
     let watchers = WatcherRepo::new(ctx.clone()).get_watchers_by_user(&user.id.uri())?;
 
+    let transfer_repo = TransferRepo::new(ctx.clone());
+    let transfers = watchers
+        .iter()
+        .map(|watcher| {
+            transfer_repo
+                .get_transfers_for_watcher(watcher.id, DASHBOARD_TRANSFER_LIMIT, 0)
+                .map(|transfers| (watcher.id, transfers))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
     // Get all playlists that belong to the user
     let user_playlists = session.client.current_user_playlists().await?;
     let user_playlist_ids = user_playlists
@@ -47,7 +60,7 @@ async fn get_current_user_dashboard(
     // Fetch the details of the playlists that the user does not own
     let missing_playlist_ids = watchers
         .iter()
-        .flat_map(|watcher| vec![&watcher.playlist_from, &watcher.playlist_to])
+        .flat_map(|watcher| watcher.sources().chain(watcher.targets()))
         .filter_map(|playlist| match playlist {
             crate::db::model::playlist::PlaylistType::Id(id) => Some(id.to_owned()),
             _ => None,
@@ -62,7 +75,9 @@ async fn get_current_user_dashboard(
     let template = DashboardTemplate {
         config: ctx.config,
         name: user.display_name,
+        time_zone: session.user.time_zone.name().to_owned(),
         watchers,
+        transfers,
         user_playlists: user_playlists
             .iter()
             .cloned()
@@ -1,8 +1,14 @@
 use crate::{
     api::{self, id::UserId},
     context::AppContext,
-    db::model::{playlist::PlaylistType, watcher::SyncInterval},
-    db::repo::watcher::WatcherRepo,
+    db::model::{
+        options::WatcherOptions,
+        playlist::PlaylistType,
+        rules::WatcherRules,
+        schedule::Schedule,
+        watcher::{NewWatcher, Watcher},
+    },
+    db::repo::{mirror::MirrorRepo, transfer::TransferRepo, user::UserRepo, watcher::WatcherRepo},
     web::{
         error::{WebError, WebResult},
         middleware::auth,
@@ -11,22 +17,34 @@ use crate::{
 };
 use axum::{
     Extension, Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     middleware,
     response::IntoResponse,
-    routing::{delete, post},
+    routing::{get, post, put},
 };
 use chrono::Utc;
+use chrono_tz::Tz;
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
+use std::collections::HashSet;
 use validator::Validate;
 
+const DEFAULT_TRANSFERS_PER_PAGE: u32 = 20;
+
 pub fn router(ctx: AppContext) -> Router {
     Router::new()
         .route("/watchers", post(create_watcher))
-        .route("/watchers/{id}", delete(delete_watcher))
+        .route("/watchers/preview", post(preview_new_watcher))
+        .route(
+            "/watchers/{id}",
+            axum::routing::patch(update_watcher).delete(delete_watcher),
+        )
         .route("/watchers/{id}/sync", post(sync_watcher))
+        .route("/watchers/{id}/preview", post(preview_watcher))
+        .route("/watchers/{id}/rules", put(update_watcher_rules))
+        .route("/watchers/{id}/options", put(update_watcher_options))
+        .route("/watchers/{id}/transfers", get(get_watcher_transfers))
         .route_layer(middleware::from_fn_with_state(
             ctx.clone(),
             auth::middleware,
@@ -39,7 +57,24 @@ struct CreateWatcherParams {
     playlist_from: String,
     playlist_to: String,
     should_remove: bool,
-    sync_interval: SyncInterval,
+    #[serde(alias = "sync_interval")]
+    schedule: String,
+    /// IANA name of the browser's time zone, which cron schedules are evaluated in
+    time_zone: Option<String>,
+    #[serde(default)]
+    #[validate(nested)]
+    rules: WatcherRules,
+    #[serde(default)]
+    #[validate(nested)]
+    options: WatcherOptions,
+    /// Further playlists to merge with `playlist_from`
+    #[serde(default)]
+    #[validate(length(max = 10))]
+    extra_sources: Vec<String>,
+    /// Further playlists to copy to along with `playlist_to`
+    #[serde(default)]
+    #[validate(length(max = 10))]
+    extra_targets: Vec<String>,
 }
 
 async fn create_watcher(
@@ -51,19 +86,209 @@ async fn create_watcher(
 
     let from = PlaylistType::try_from_value(&data.playlist_from)?;
     let to = PlaylistType::try_from_value(&data.playlist_to)?;
+    let extra_sources = parse_playlists(&data.extra_sources)?;
+    let extra_targets = parse_playlists(&data.extra_targets)?;
+    let schedule = parse_schedule(&data.schedule)?;
+
+    check_playlists(
+        [&from].into_iter().chain(&extra_sources),
+        [&to].into_iter().chain(&extra_targets),
+    )?;
+    check_mirror(
+        &data.options,
+        &data.rules,
+        &from,
+        &to,
+        !extra_sources.is_empty() || !extra_targets.is_empty(),
+    )?;
+    check_max_tracks(
+        &data.options,
+        [&from].into_iter().chain(&extra_sources),
+        [&to].into_iter().chain(&extra_targets),
+    )?;
+
+    let repo = WatcherRepo::new(ctx.clone());
+
+    // Mirrors remove tracks from both of their playlists
+    let should_remove = data.should_remove || data.options.mirror;
+    for from in [&from].into_iter().chain(&extra_sources) {
+        validate_watcher(&session, &repo, from, should_remove, None).await?;
+    }
+    if data.options.mirror {
+        validate_watcher(&session, &repo, &to, true, None).await?;
+    }
+    update_time_zone(&ctx, &session, data.time_zone.as_deref())?;
+
+    repo.create_watcher(&NewWatcher {
+        should_remove: data.should_remove,
+        schedule,
+        rules: data.rules,
+        options: data.options,
+        extra_sources,
+        extra_targets,
+        ..NewWatcher::new(&session.user.user_uri, from, to)
+    })
+    .map_err(map_duplicate_watcher_error)?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Parse a schedule from form data, e.g. `every 6 hours` or `cron 0 6 * * mon`
+fn parse_schedule(value: &str) -> WebResult<Schedule> {
+    value.parse().map_err(|_| {
+        WebError::InvalidFormData(format!(
+            "\"{}\" is not a valid schedule. Use an interval like \"every 6 hours\" or a cron expression like \"0 6 * * mon\".",
+            value
+        ))
+    })
+}
+
+/// Parse a list of playlists from form data
+fn parse_playlists(values: &[String]) -> WebResult<Vec<PlaylistType>> {
+    Ok(values
+        .iter()
+        .map(|value| PlaylistType::try_from_value(value))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// A watcher may not transfer between the same playlist, nor list a playlist twice. Dated playlists
+/// only exist once tracks are transferred to them, so they can't be a source.
+fn check_playlists<'a>(
+    sources: impl IntoIterator<Item = &'a PlaylistType>,
+    targets: impl IntoIterator<Item = &'a PlaylistType>,
+) -> WebResult<()> {
+    let sources = sources.into_iter().collect::<Vec<_>>();
+    if sources.iter().any(|playlist| matches!(playlist, PlaylistType::Template(_))) {
+        return Err(WebError::InvalidFormData(
+            "A dated playlist can only be transferred to.".into(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
 
-    if to == from {
+    if !sources.into_iter().chain(targets).all(|playlist| seen.insert(playlist)) {
         return Err(WebError::InvalidFormData(
             "Cannot create watcher that transfers between the same playlist.".into(),
         ));
     }
 
-    let repo = WatcherRepo::new(ctx.clone());
+    Ok(())
+}
+
+/// Mirrors keep every track in both of their playlists, so they can't filter tracks, and only ever
+/// mirror two of the user's playlists
+fn check_mirror(
+    options: &WatcherOptions,
+    rules: &WatcherRules,
+    from: &PlaylistType,
+    to: &PlaylistType,
+    has_extra_playlists: bool,
+) -> WebResult<()> {
+    if !options.mirror {
+        return Ok(());
+    }
+
+    if !matches!(from, PlaylistType::Id(_)) || !matches!(to, PlaylistType::Id(_)) {
+        return Err(WebError::InvalidFormData(
+            "Only playlists can be mirrored, not Liked Tracks or a dated playlist.".into(),
+        ));
+    }
+
+    if has_extra_playlists {
+        return Err(WebError::InvalidFormData(
+            "A mirror keeps exactly two playlists in step, so it cannot have more playlists."
+                .into(),
+        ));
+    }
+
+    if !rules.is_empty() {
+        return Err(WebError::InvalidFormData(
+            "A mirror keeps every track in both playlists, so it cannot have filter rules.".into(),
+        ));
+    }
 
-    let existing_watchers = repo.get_watchers_for_playlist(&from)?;
+    Ok(())
+}
+
+/// Only playlists can be capped, and evicted tracks have to leave the watcher's playlists
+fn check_max_tracks<'a>(
+    options: &WatcherOptions,
+    sources: impl IntoIterator<Item = &'a PlaylistType>,
+    targets: impl IntoIterator<Item = &'a PlaylistType>,
+) -> WebResult<()> {
+    let targets = targets.into_iter().collect::<Vec<_>>();
+
+    if options.max_tracks.is_some() && options.mirror {
+        return Err(WebError::InvalidFormData(
+            "A mirror keeps every track in both playlists, so it cannot have a maximum number of tracks.".into(),
+        ));
+    }
+
+    if options.max_tracks.is_some()
+        && !targets.iter().all(|playlist| matches!(playlist, PlaylistType::Id(_)))
+    {
+        return Err(WebError::InvalidFormData(
+            "Only playlists can have a maximum number of tracks, not Liked Tracks or a dated playlist."
+                .into(),
+        ));
+    }
+
+    if let Some(overflow) = &options.overflow_playlist {
+        if options.max_tracks.is_none() {
+            return Err(WebError::InvalidFormData(
+                "Set a maximum number of tracks to move evicted tracks to another playlist.".into(),
+            ));
+        }
+
+        let overflow = PlaylistType::Id(overflow.clone());
+        if sources.into_iter().chain(targets).any(|playlist| *playlist == overflow) {
+            return Err(WebError::InvalidFormData(
+                "Evicted tracks must be moved to a playlist the watcher doesn't use.".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Remember the user's time zone when the dashboard sends one, returning the zone to schedule in
+fn update_time_zone(
+    ctx: &AppContext,
+    session: &session::Session,
+    time_zone: Option<&str>,
+) -> WebResult<Tz> {
+    let Some(time_zone) = time_zone else {
+        return Ok(session.user.time_zone);
+    };
+
+    let time_zone = time_zone
+        .parse::<Tz>()
+        .map_err(|_| WebError::InvalidFormData(format!("Unknown time zone \"{}\".", time_zone)))?;
+
+    if time_zone != session.user.time_zone {
+        UserRepo::new(ctx.clone()).update_user_time_zone(&session.user.user_uri, time_zone)?;
+    }
+
+    Ok(time_zone)
+}
+
+/// Check a watcher's source against the user's other watchers and their permissions on Spotify.
+/// When editing, `ignore_id` is the watcher being edited so it doesn't conflict with itself.
+async fn validate_watcher(
+    session: &session::Session,
+    repo: &WatcherRepo,
+    from: &PlaylistType,
+    should_remove: bool,
+    ignore_id: Option<u32>,
+) -> WebResult<()> {
+    let existing_watchers = repo
+        .get_watchers_for_playlist(from)?
+        .into_iter()
+        .filter(|watcher| Some(watcher.id) != ignore_id)
+        .collect::<Vec<_>>();
     let existing_mutable_watchers = existing_watchers
         .iter()
-        .filter(|watcher| watcher.should_remove)
+        .filter(|watcher| watcher.should_remove || watcher.options.mirror)
         .collect::<Vec<_>>();
 
     if !existing_mutable_watchers.is_empty() {
@@ -72,16 +297,16 @@ async fn create_watcher(
         ));
     }
 
-    if data.should_remove && !existing_watchers.is_empty() {
+    if should_remove && !existing_watchers.is_empty() {
         return Err(WebError::InvalidFormData(
             "A watcher already exists for this playlist. Disable track removal or remove the other watcher.".into(),
         ));
     }
 
-    if let PlaylistType::Id(id) = &from {
+    if let PlaylistType::Id(id) = from {
         let user_id = UserId::parse_from_input(&session.user.user_uri)?;
         match api::util::check_playlist_editable(&session.client, id, &user_id).await {
-            Ok(false) if data.should_remove => return Err(WebError::InvalidFormData(
+            Ok(false) if should_remove => return Err(WebError::InvalidFormData(
                 "You do not have permission to edit the source playlist. You must disable track removal.".into(),
             )),
             Ok(_) => {}
@@ -93,23 +318,19 @@ async fn create_watcher(
         };
     }
 
-    repo.create_watcher(
-        &session.user.user_uri,
-        &from,
-        &to,
-        data.should_remove,
-        data.sync_interval,
-    )
-    .map_err(|err| match err {
+    Ok(())
+}
+
+/// Watchers are unique per user and pair of playlists
+fn map_duplicate_watcher_error(err: crate::db::error::DbError) -> WebError {
+    match err {
         crate::db::error::DbError::SQLiteError(
             ref _inner @ r2d2_sqlite::rusqlite::Error::SqliteFailure(ref err_code, _),
         ) if err_code.code == r2d2_sqlite::rusqlite::ErrorCode::ConstraintViolation => {
             WebError::InvalidFormData("Watcher already exists for these playlists.".into())
         }
         _ => err.into(),
-    })?;
-
-    Ok(Json(json!({ "success": true })))
+    }
 }
 
 #[derive(Deserialize)]
@@ -117,12 +338,127 @@ struct ManageWatcherParams {
     id: u32,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+struct UpdateWatcherParams {
+    playlist_to: Option<String>,
+    should_remove: Option<bool>,
+    #[serde(alias = "sync_interval")]
+    schedule: Option<String>,
+    time_zone: Option<String>,
+    enabled: Option<bool>,
+    #[validate(length(max = 10))]
+    extra_sources: Option<Vec<String>>,
+    #[validate(length(max = 10))]
+    extra_targets: Option<Vec<String>>,
+}
+
+/// Change a watcher's settings in place, keeping its transfer history
+async fn update_watcher(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageWatcherParams>,
+    Json(data): Json<UpdateWatcherParams>,
+) -> WebResult<impl IntoResponse> {
+    data.validate()?;
+
+    let repo = WatcherRepo::new(ctx.clone());
+
+    let watcher = match repo.get_watcher_by_id_and_user(params.id, &session.user.user_uri)? {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    // Pausing or resuming alone doesn't re-check the playlists, so a broken watcher can always be paused
+    let extras_changed = data.extra_sources.is_some() || data.extra_targets.is_some();
+    if data.playlist_to.is_some()
+        || data.should_remove.is_some()
+        || data.schedule.is_some()
+        || extras_changed
+    {
+        let to = match &data.playlist_to {
+            Some(to) => PlaylistType::try_from_value(to)?,
+            None => watcher.playlist_to.clone(),
+        };
+        let should_remove = data.should_remove.unwrap_or(watcher.should_remove);
+        let schedule = match &data.schedule {
+            Some(schedule) => parse_schedule(schedule)?,
+            None => watcher.schedule.clone(),
+        };
+        let extra_sources = match &data.extra_sources {
+            Some(sources) => parse_playlists(sources)?,
+            None => watcher.extra_sources.clone(),
+        };
+        let extra_targets = match &data.extra_targets {
+            Some(targets) => parse_playlists(targets)?,
+            None => watcher.extra_targets.clone(),
+        };
+
+        check_playlists(
+            [&watcher.playlist_from].into_iter().chain(&extra_sources),
+            [&to].into_iter().chain(&extra_targets),
+        )?;
+        check_mirror(
+            &watcher.options,
+            &watcher.rules,
+            &watcher.playlist_from,
+            &to,
+            !extra_sources.is_empty() || !extra_targets.is_empty(),
+        )?;
+        check_max_tracks(
+            &watcher.options,
+            [&watcher.playlist_from].into_iter().chain(&extra_sources),
+            [&to].into_iter().chain(&extra_targets),
+        )?;
+
+        let mirror = watcher.options.mirror;
+        for from in [&watcher.playlist_from].into_iter().chain(&extra_sources) {
+            validate_watcher(
+                &session,
+                &repo,
+                from,
+                should_remove || mirror,
+                Some(watcher.id),
+            )
+            .await?;
+        }
+        if mirror {
+            validate_watcher(&session, &repo, &to, true, Some(watcher.id)).await?;
+        }
+        let time_zone = update_time_zone(&ctx, &session, data.time_zone.as_deref())?;
+
+        // The next sync moves with the schedule so a shorter interval takes effect straight away
+        let next_sync_at = watcher
+            .last_sync_at
+            .map(|last_sync_at| schedule.next_after(last_sync_at, time_zone));
+
+        repo.update_watcher(watcher.id, &to, should_remove, &schedule, next_sync_at)
+            .map_err(map_duplicate_watcher_error)?;
+
+        if extras_changed {
+            repo.update_watcher_extra_playlists(watcher.id, &extra_sources, &extra_targets)?;
+        }
+
+        // What the mirror knew about the old playlist says nothing about the new one
+        if to != watcher.playlist_to {
+            MirrorRepo::new(ctx.clone()).delete_state(watcher.id)?;
+        }
+    }
+
+    if let Some(enabled) = data.enabled
+        && enabled != watcher.enabled
+    {
+        repo.update_watcher_enabled(watcher.id, enabled, None)?;
+    }
+
+    Ok(Json(json!({ "success": true })))
+}
+
 async fn delete_watcher(
     Extension(session): Extension<session::Session>,
     State(ctx): State<AppContext>,
     Path(params): Path<ManageWatcherParams>,
 ) -> WebResult<impl IntoResponse> {
-    let repo = WatcherRepo::new(ctx);
+    let repo = WatcherRepo::new(ctx.clone());
 
     let watcher = match repo.get_watcher_by_id_and_user(params.id, &session.user.user_uri)? {
         Some(val) => val,
@@ -134,6 +470,7 @@ async fn delete_watcher(
         &watcher.playlist_from,
         &watcher.playlist_to,
     )?;
+    MirrorRepo::new(ctx).delete_state(watcher.id)?;
 
     Ok(Json(json!({ "success": true })))
 }
@@ -151,11 +488,935 @@ async fn sync_watcher(
             None => return Err(WebError::NotFoundError),
         };
 
-    let count =
-        crate::sync::sync_watcher(ctx, session.client, &watcher_repo, &watcher, Utc::now()).await?;
+    let count = crate::sync::sync_watcher(
+        ctx,
+        session.client,
+        &watcher_repo,
+        &watcher,
+        Utc::now(),
+        session.user.time_zone,
+    )
+    .await?;
 
     Ok(Json(json!({
         "success": true,
         "num_tracks_transferred": count
     })))
 }
+
+async fn update_watcher_rules(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageWatcherParams>,
+    Json(rules): Json<WatcherRules>,
+) -> WebResult<impl IntoResponse> {
+    rules.validate()?;
+
+    let repo = WatcherRepo::new(ctx);
+
+    let watcher = match repo.get_watcher_by_id_and_user(params.id, &session.user.user_uri)? {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    check_mirror(
+        &watcher.options,
+        &rules,
+        &watcher.playlist_from,
+        &watcher.playlist_to,
+        !watcher.extra_sources.is_empty() || !watcher.extra_targets.is_empty(),
+    )?;
+
+    repo.update_watcher_rules(watcher.id, &rules)?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn update_watcher_options(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageWatcherParams>,
+    Json(options): Json<WatcherOptions>,
+) -> WebResult<impl IntoResponse> {
+    options.validate()?;
+
+    let repo = WatcherRepo::new(ctx.clone());
+
+    let watcher = match repo.get_watcher_by_id_and_user(params.id, &session.user.user_uri)? {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    check_mirror(
+        &options,
+        &watcher.rules,
+        &watcher.playlist_from,
+        &watcher.playlist_to,
+        !watcher.extra_sources.is_empty() || !watcher.extra_targets.is_empty(),
+    )?;
+    check_max_tracks(&options, watcher.sources(), watcher.targets())?;
+
+    // A mirror that is switched on starts afresh, as either playlist may have changed since it was last on
+    let starts_mirroring = options.mirror && !watcher.options.mirror;
+    if starts_mirroring {
+        for playlist in [&watcher.playlist_from, &watcher.playlist_to] {
+            validate_watcher(&session, &repo, playlist, true, Some(watcher.id)).await?;
+        }
+        MirrorRepo::new(ctx).delete_state(watcher.id)?;
+    }
+
+    repo.update_watcher_options(watcher.id, &options)?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn preview_watcher(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageWatcherParams>,
+) -> WebResult<impl IntoResponse> {
+    let watcher = match WatcherRepo::new(ctx.clone())
+        .get_watcher_by_id_and_user(params.id, &session.user.user_uri)?
+    {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    let preview = crate::sync::preview::preview_watcher(
+        ctx,
+        session.client,
+        &watcher,
+        session.user.time_zone,
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "preview": preview,
+    })))
+}
+
+/// Preview a watcher from the create form before it has been saved
+async fn preview_new_watcher(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Json(data): Json<CreateWatcherParams>,
+) -> WebResult<impl IntoResponse> {
+    data.validate()?;
+
+    let watcher = Watcher {
+        id: 0,
+        user_uri: session.user.user_uri.clone(),
+        playlist_from: PlaylistType::try_from_value(&data.playlist_from)?,
+        playlist_to: PlaylistType::try_from_value(&data.playlist_to)?,
+        should_remove: data.should_remove,
+        schedule: parse_schedule(&data.schedule)?,
+        last_sync_at: None,
+        next_sync_at: None,
+        created_at: Utc::now(),
+        rules: data.rules,
+        enabled: true,
+        paused_reason: None,
+        consecutive_failures: 0,
+        options: data.options,
+        snapshots: None,
+        extra_sources: parse_playlists(&data.extra_sources)?,
+        extra_targets: parse_playlists(&data.extra_targets)?,
+        period_playlists: vec![],
+    };
+
+    check_playlists(watcher.sources(), watcher.targets())?;
+    check_mirror(
+        &watcher.options,
+        &watcher.rules,
+        &watcher.playlist_from,
+        &watcher.playlist_to,
+        !watcher.extra_sources.is_empty() || !watcher.extra_targets.is_empty(),
+    )?;
+    check_max_tracks(&watcher.options, watcher.sources(), watcher.targets())?;
+
+    let preview = crate::sync::preview::preview_watcher(
+        ctx,
+        session.client,
+        &watcher,
+        session.user.time_zone,
+    )
+    .await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "preview": preview,
+    })))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct TransferHistoryParams {
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1))]
+    page: u32,
+    #[serde(default = "default_per_page")]
+    #[validate(range(min = 1, max = 100))]
+    per_page: u32,
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    DEFAULT_TRANSFERS_PER_PAGE
+}
+
+async fn get_watcher_transfers(
+    Extension(session): Extension<session::Session>,
+    State(ctx): State<AppContext>,
+    Path(params): Path<ManageWatcherParams>,
+    Query(query): Query<TransferHistoryParams>,
+) -> WebResult<impl IntoResponse> {
+    query.validate()?;
+
+    let watcher = match WatcherRepo::new(ctx.clone())
+        .get_watcher_by_id_and_user(params.id, &session.user.user_uri)?
+    {
+        Some(val) => val,
+        None => return Err(WebError::NotFoundError),
+    };
+
+    let transfer_repo = TransferRepo::new(ctx);
+    let total = transfer_repo.count_transfers_for_watcher(watcher.id)?;
+    let transfers = transfer_repo.get_transfers_for_watcher(
+        watcher.id,
+        query.per_page,
+        (query.page - 1).saturating_mul(query.per_page),
+    )?;
+
+    Ok(Json(json!({
+        "success": true,
+        "transfers": transfers,
+        "total": total,
+        "page": query.page,
+        "per_page": query.per_page,
+    })))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        db::{
+            model::{
+                options::{ConflictPolicy, EvictionPolicy, InsertOrder},
+                playlist::PlaylistType,
+                schedule::Schedule,
+            },
+            repo::{transfer::TransferRepo, user::UserRepo, watcher::WatcherRepo},
+        },
+        testing::TEST_USER_ID,
+        web::router::testing::TestServer,
+    };
+    use chrono_tz::Tz;
+    use reqwest::{Method, StatusCode, header};
+    use serde_json::{Value, json};
+
+    const SOURCE: &str = "SourcePlaylist00000000";
+    const TARGET: &str = "TargetPlaylist00000000";
+
+    fn uri(id: &str) -> String {
+        format!("spotify:playlist:{}", id)
+    }
+
+    #[tokio::test]
+    async fn it_redirects_guests() {
+        let server = TestServer::start().await;
+
+        let res = server.http.post(format!("{}/watchers", server.url)).send().await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+    }
+
+    #[tokio::test]
+    async fn it_creates_a_watcher() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, body) = server.create_watcher("_liked", &uri(TARGET), true).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["success"], true);
+
+        let watchers = WatcherRepo::new(server.app.ctx.clone())
+            .get_watchers_by_user(&server.app.user.user_uri)
+            .unwrap();
+        assert_eq!(watchers.len(), 1);
+        assert_eq!(watchers[0].playlist_from, PlaylistType::Saved);
+        assert!(watchers[0].should_remove);
+    }
+
+    #[tokio::test]
+    async fn it_rejects_watchers_between_the_same_playlist() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, _) = server.create_watcher(&uri(TARGET), &uri(TARGET), false).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_rejects_a_second_watcher_when_removal_is_enabled() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, _) = server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = server.create_watcher(&uri(SOURCE), "_liked", false).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_rejects_removal_from_playlists_the_user_cannot_edit() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, "someone_else", &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, _) = server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = server.create_watcher(&uri(SOURCE), &uri(TARGET), false).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn it_syncs_a_watcher_on_demand() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+
+        let id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        let (status, body) =
+            server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["num_tracks_transferred"], 2);
+        assert_eq!(server.app.spotify.playlist_track_ids(TARGET).len(), 2);
+        assert!(server.app.spotify.playlist_track_ids(SOURCE).is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_previews_a_watcher_without_changing_anything() {
+        let local = "spotify:local:Some+Artist:Some+Album:Some+Song:180";
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(
+            SOURCE,
+            TEST_USER_ID,
+            &["a", "b", "spotify:episode:ep1", local],
+        );
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &["b"]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+
+        let id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        let (status, body) =
+            server.request(Method::POST, &format!("/watchers/{}/preview", id), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let preview = &body["preview"];
+        assert_eq!(
+            preview["added"][0]["id"],
+            json!({"type": "track", "id": "a"})
+        );
+        assert_eq!(preview["added"][0]["name"], "Track a");
+        assert_eq!(
+            preview["added"][1]["id"],
+            json!({"type": "episode", "id": "ep1"})
+        );
+        assert_eq!(preview["added"][1]["name"], "Episode ep1");
+        assert_eq!(preview["added"][1]["artists"], json!(["Fake Show"]));
+        assert_eq!(preview["skipped_duplicates"][0]["id"]["id"], "b");
+        assert_eq!(preview["removed"].as_array().unwrap().len(), 3);
+        assert_eq!(preview["skipped_local"][0]["id"]["id"], local);
+        assert_eq!(preview["skipped_local"][0]["name"], "Some Song");
+        assert_eq!(
+            preview["skipped_local"][0]["artists"],
+            json!(["Some Artist"])
+        );
+
+        assert_eq!(
+            server.app.spotify.playlist_track_ids(SOURCE),
+            vec!["a", "b", "spotify:episode:ep1", local]
+        );
+        assert_eq!(server.app.spotify.playlist_track_ids(TARGET), vec!["b"]);
+        assert!(
+            TransferRepo::new(server.app.ctx.clone())
+                .get_transfers_for_watcher(id, 10, 0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_previews_a_watcher_before_creating_it() {
+        let server = TestServer::start().await;
+        server.app.spotify.set_saved_tracks(&["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, body) = server
+            .request(
+                Method::POST,
+                "/watchers/preview",
+                Some(json!({
+                    "playlist_from": "_liked",
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "sync_interval": "hour",
+                })),
+            )
+            .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["preview"]["added"].as_array().unwrap().len(), 2);
+        assert!(body["preview"]["removed"].as_array().unwrap().is_empty());
+        assert!(server.app.spotify.playlist_track_ids(TARGET).is_empty());
+        assert!(WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_saves_and_validates_watcher_rules() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": "_liked",
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "sync_interval": "hour",
+                    "rules": { "min_duration_secs": 300, "max_duration_secs": 100 },
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        server.create_watcher("_liked", &uri(TARGET), false).await;
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/rules", id),
+                Some(json!({ "exclude_explicit": true, "artists_exclude": ["Someone"] })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let rules = repo.get_all_watchers().unwrap().remove(0).rules;
+        assert!(rules.exclude_explicit);
+        assert_eq!(rules.artists_exclude, vec!["Someone"]);
+
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/rules", id),
+                Some(json!({ "added_within_days": 0 })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_saves_watcher_options() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": "_liked",
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "schedule": "every 1h",
+                    "options": { "insert_order": "prepend" },
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert_eq!(watcher.options.insert_order, InsertOrder::Prepend);
+        assert!(!watcher.options.keep_duplicates);
+
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/options", watcher.id),
+                Some(json!({ "insert_order": "sort_by_album", "keep_duplicates": true })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let options = repo.get_all_watchers().unwrap().remove(0).options;
+        assert_eq!(options.insert_order, InsertOrder::SortByAlbum);
+        assert!(options.keep_duplicates);
+
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/options", watcher.id),
+                Some(json!({ "insert_order": "shuffle" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_updates_a_watcher_in_place() {
+        let other = "OtherPlaylist000000000";
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(other, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), false).await;
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+
+        let (status, _) = server
+            .request(
+                Method::PATCH,
+                &format!("/watchers/{}", id),
+                Some(json!({
+                    "playlist_to": uri(other),
+                    "should_remove": true,
+                    "schedule": "every 1 week",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let watcher = repo.get_watcher_by_id(id).unwrap().unwrap();
+        assert_eq!(
+            watcher.playlist_to,
+            PlaylistType::try_from_value(&uri(other)).unwrap()
+        );
+        assert!(watcher.should_remove);
+        assert_eq!(watcher.schedule, Schedule::Every(7 * 24 * 60));
+
+        // Fields left out are kept as they are
+        let (status, _) = server
+            .request(
+                Method::PATCH,
+                &format!("/watchers/{}", id),
+                Some(json!({ "schedule": "every 12 hours" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let watcher = repo.get_watcher_by_id(id).unwrap().unwrap();
+        assert!(watcher.should_remove);
+        assert_eq!(watcher.schedule, Schedule::Every(12 * 60));
+
+        let (status, _) = server
+            .request(
+                Method::PATCH,
+                &format!("/watchers/{}", id + 1),
+                Some(json!({ "schedule": "every day" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn it_creates_and_updates_watchers_with_several_playlists() {
+        let other = "OtherPlaylist000000000";
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(other, TEST_USER_ID, &[]);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": uri(SOURCE),
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "sync_interval": "hour",
+                    "extra_sources": ["_liked"],
+                    "extra_targets": [uri(SOURCE)],
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": uri(SOURCE),
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "sync_interval": "hour",
+                    "extra_sources": ["_liked"],
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert_eq!(watcher.extra_sources, vec![PlaylistType::Saved]);
+        assert!(watcher.extra_targets.is_empty());
+
+        let (status, _) = server
+            .request(
+                Method::PATCH,
+                &format!("/watchers/{}", watcher.id),
+                Some(json!({ "extra_sources": [], "extra_targets": [uri(other)] })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let watcher = repo.get_watcher_by_id(watcher.id).unwrap().unwrap();
+        assert!(watcher.extra_sources.is_empty());
+        assert_eq!(
+            watcher.extra_targets,
+            vec![PlaylistType::try_from_value(&uri(other)).unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_only_creates_mirrors_that_can_keep_both_playlists_identical() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let mirror = |from: &str, rules: Value| {
+            json!({
+                "playlist_from": from,
+                "playlist_to": uri(TARGET),
+                "should_remove": false,
+                "sync_interval": "hour",
+                "rules": rules,
+                "options": { "mirror": true, "conflict_policy": "prefer_target" },
+            })
+        };
+
+        let (status, _) = server
+            .request(Method::POST, "/watchers", Some(mirror("_liked", json!({}))))
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(mirror(&uri(SOURCE), json!({ "min_duration_secs": 60 }))),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = server
+            .request(
+                Method::POST,
+                "/watchers",
+                Some(mirror(&uri(SOURCE), json!({}))),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let watcher = repo.get_all_watchers().unwrap().remove(0);
+        assert!(watcher.options.mirror);
+        assert_eq!(
+            watcher.options.conflict_policy,
+            ConflictPolicy::PreferTarget
+        );
+
+        let (status, _) = server
+            .request(
+                Method::PUT,
+                &format!("/watchers/{}/rules", watcher.id),
+                Some(json!({ "min_duration_secs": 60 })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The mirror removes tracks from its target, so it can't be another watcher's source
+        let (status, _) = server.create_watcher(&uri(TARGET), "_liked", false).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_only_transfers_to_valid_playlist_templates() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+
+        let (status, _) = server.create_watcher("template:Liked {year}", &uri(SOURCE), false).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) =
+            server.create_watcher(&uri(SOURCE), "template:Liked {decade}", false).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = server
+            .create_watcher(&uri(SOURCE), "template:Liked {year}-{month}", false)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let watcher =
+            WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        assert_eq!(
+            watcher.playlist_to.to_value(),
+            "template:Liked {year}-{month}"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_only_limits_the_size_of_playlists() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        let create = |to: String, options: Value| {
+            server.request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": uri(SOURCE),
+                    "playlist_to": to,
+                    "should_remove": false,
+                    "sync_interval": "hour",
+                    "options": options,
+                })),
+            )
+        };
+
+        let (status, _) = create("_liked".into(), json!({ "max_tracks": 100 })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = create(uri(TARGET), json!({ "overflow_playlist": SOURCE })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = create(
+            uri(TARGET),
+            json!({ "max_tracks": 100, "overflow_playlist": SOURCE }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = create(uri(TARGET), json!({ "max_tracks": 0 })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = create(
+            uri(TARGET),
+            json!({ "max_tracks": 100, "eviction_policy": "random" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let watcher =
+            WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap().remove(0);
+        assert_eq!(watcher.options.max_tracks, Some(100));
+        assert_eq!(watcher.options.eviction_policy, EvictionPolicy::Random);
+    }
+
+    #[tokio::test]
+    async fn it_schedules_watchers_in_the_users_time_zone() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+
+        let create = |schedule: &str, time_zone: &str| {
+            server.request(
+                Method::POST,
+                "/watchers",
+                Some(json!({
+                    "playlist_from": uri(SOURCE),
+                    "playlist_to": uri(TARGET),
+                    "should_remove": false,
+                    "schedule": schedule,
+                    "time_zone": time_zone,
+                })),
+            )
+        };
+
+        let (status, _) = create("every 2 years", "Europe/London").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _) = create("0 6 * * mon", "Mars/Olympus_Mons").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = create("0 6 * * mon", "Europe/London").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let watcher = &WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0];
+        assert_eq!(watcher.schedule, Schedule::Cron("0 6 * * mon".into()));
+
+        let user = UserRepo::new(server.app.ctx.clone())
+            .find_user_by_uri(&server.app.user.user_uri)
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.time_zone, Tz::Europe__London);
+    }
+
+    #[tokio::test]
+    async fn it_validates_watcher_updates_like_new_watchers() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, "someone_else", &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), false).await;
+        server.create_watcher(&uri(SOURCE), "_liked", false).await;
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+        let path = format!("/watchers/{}", id);
+        let update = |body: Value| server.request(Method::PATCH, &path, Some(body));
+
+        // Another watcher already reads from the source
+        let (status, _) = update(json!({ "should_remove": true })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The user can't remove tracks from a playlist they don't own
+        server.request(Method::DELETE, &format!("/watchers/{}", id + 1), None).await;
+        let (status, _) = update(json!({ "should_remove": true })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = update(json!({ "playlist_to": uri(SOURCE) })).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let watcher = repo.get_watcher_by_id(id).unwrap().unwrap();
+        assert!(!watcher.should_remove);
+        assert_eq!(
+            watcher.playlist_to,
+            PlaylistType::try_from_value(&uri(TARGET)).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_pauses_and_resumes_a_watcher() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+        let path = format!("/watchers/{}", id);
+
+        // Pausing works even when the source playlist has since been deleted
+        server.app.spotify.state().playlists.remove(SOURCE);
+        let (status, _) =
+            server.request(Method::PATCH, &path, Some(json!({ "enabled": false }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!repo.get_watcher_by_id(id).unwrap().unwrap().enabled);
+
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &[]);
+        let dashboard = server
+            .http
+            .get(format!("{}/me", server.url))
+            .header(header::COOKIE, &server.cookie)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(dashboard.contains("Paused."));
+        assert!(dashboard.contains("Resume"));
+
+        let (status, _) =
+            server.request(Method::PATCH, &path, Some(json!({ "enabled": true }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(repo.get_watcher_by_id(id).unwrap().unwrap().enabled);
+    }
+
+    #[tokio::test]
+    async fn it_deletes_a_watcher() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher("_liked", &uri(TARGET), false).await;
+
+        let repo = WatcherRepo::new(server.app.ctx.clone());
+        let id = repo.get_all_watchers().unwrap()[0].id;
+
+        let (status, _) = server.request(Method::DELETE, &format!("/watchers/{}", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(repo.get_all_watchers().unwrap().is_empty());
+
+        let (status, _) = server.request(Method::DELETE, &format!("/watchers/{}", id), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn it_lists_transfer_history_newest_first() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), true).await;
+
+        let id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["c"]);
+        server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+
+        let (status, body) = server
+            .request(
+                Method::GET,
+                &format!("/watchers/{}/transfers?per_page=1", id),
+                None,
+            )
+            .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["transfers"].as_array().unwrap().len(), 1);
+        assert_eq!(body["transfers"][0]["num_tracks_transferred"], 1);
+        assert_eq!(body["transfers"][0]["error"], Value::Null);
+
+        let (_, body) = server
+            .request(
+                Method::GET,
+                &format!("/watchers/{}/transfers?page=2&per_page=1", id),
+                None,
+            )
+            .await;
+        assert_eq!(body["transfers"][0]["num_tracks_transferred"], 2);
+
+        let (status, _) = server
+            .request(
+                Method::GET,
+                &format!("/watchers/{}/transfers?page=0", id),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn it_shows_recent_transfers_on_the_dashboard() {
+        let server = TestServer::start().await;
+        server.app.spotify.add_playlist(SOURCE, TEST_USER_ID, &["a", "b"]);
+        server.app.spotify.add_playlist(TARGET, TEST_USER_ID, &[]);
+        server.create_watcher(&uri(SOURCE), &uri(TARGET), false).await;
+
+        let id = WatcherRepo::new(server.app.ctx.clone()).get_all_watchers().unwrap()[0].id;
+        server.request(Method::POST, &format!("/watchers/{}/sync", id), None).await;
+
+        let res = server
+            .http
+            .get(format!("{}/me", server.url))
+            .header(header::COOKIE, &server.cookie)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await.unwrap().contains("Transferred 2 tracks"));
+    }
+}
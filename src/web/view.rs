@@ -1,13 +1,14 @@
 use crate::{
     api::{
-        SPOTIFY_LIKED_TRACKS_URL,
+        SPOTIFY_LIKED_TRACKS_URL, SPOTIFY_PLAYLISTS_URL,
         id::PlaylistId,
         model::{Image, PlaylistPartial},
     },
     config::ModulateConfig,
-    db::model::{playlist::PlaylistType, watcher::Watcher},
+    db::model::{playlist::PlaylistType, transfer::Transfer, watcher::Watcher},
 };
 use askama::Template;
+use std::collections::HashMap;
 
 #[derive(Template)]
 #[template(path = "connect.html")]
@@ -20,7 +21,10 @@ pub struct ConnectTemplate {
 pub struct DashboardTemplate {
     pub config: ModulateConfig,
     pub name: String,
+    /// Time zone that cron schedules are evaluated in
+    pub time_zone: String,
     pub watchers: Vec<Watcher>,
+    pub transfers: HashMap<u32, Vec<Transfer>>,
     pub all_playlists: Vec<DisplayPlaylist>,
     pub user_playlists: Vec<DisplayPlaylist>,
 }
@@ -40,16 +44,26 @@ struct PlaylistItem {
 }
 
 impl DashboardTemplate {
+    /// Look up the display data of every source and target of a watcher
     fn get_mapped_display_data(
         &self,
         watcher: &Watcher,
-    ) -> (Option<PlaylistItem>, Option<PlaylistItem>) {
+    ) -> (Vec<Option<PlaylistItem>>, Vec<Option<PlaylistItem>>) {
         (
-            self.map_display_data(&watcher.playlist_from),
-            self.map_display_data(&watcher.playlist_to),
+            watcher.sources().map(|playlist| self.map_display_data(playlist)).collect(),
+            watcher.targets().map(|playlist| self.map_display_data(playlist)).collect(),
         )
     }
 
+    /// Render an optional rule value as a form input value
+    fn optional_value<T: std::fmt::Display>(value: &Option<T>) -> String {
+        value.as_ref().map(ToString::to_string).unwrap_or_default()
+    }
+
+    fn get_recent_transfers(&self, watcher: &Watcher) -> &[Transfer] {
+        self.transfers.get(&watcher.id).map(Vec::as_slice).unwrap_or_default()
+    }
+
     fn map_display_data(&self, playlist: &PlaylistType) -> Option<PlaylistItem> {
         match playlist {
             PlaylistType::Saved => Some(PlaylistItem {
@@ -69,6 +83,17 @@ impl DashboardTemplate {
                     kind: playlist.clone(),
                     display: display.clone(),
                 }),
+
+            // The playlist changes with every period, so the template stands in for it
+            PlaylistType::Template(_) => Some(PlaylistItem {
+                kind: playlist.clone(),
+                display: DisplayPlaylist {
+                    id: None,
+                    name: playlist.to_string(),
+                    image_url: None,
+                    spotify_url: SPOTIFY_PLAYLISTS_URL.into(),
+                },
+            }),
         }
     }
 }
@@ -6,7 +6,11 @@ const headers = {
 const deleteMessage =
   "Are you sure? This will delete your connected data and watchers. Your Spotify account will be untouched, and you can always reconnect later";
 
+/** The browser's time zone, which cron schedules run in */
+const timeZone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+
 let manualEntry = false;
+let datedTarget = false;
 
 /** @param {string} message */
 function setError(message) {
@@ -48,16 +52,250 @@ function togglePlaylistInput(e) {
   return false;
 }
 
+function toggleTargetInput() {
+  datedTarget = !datedTarget;
+
+  const select = document.querySelector("#select-playlist-to");
+  const input = document.querySelector("#input-playlist-template");
+
+  select.classList.toggle("hidden");
+  input.classList.toggle("hidden");
+  input.value = "";
+
+  document.querySelector("#toggle-target-text").innerHTML = datedTarget
+    ? "Select playlist"
+    : "Use a dated playlist";
+
+  (datedTarget ? input : select).focus();
+  onInputUpdate();
+
+  return false;
+}
+
+/** The target picked in the create form, either a playlist or a template for a dated playlist */
+function getPlaylistTo() {
+  if (!datedTarget) {
+    return document.querySelector("#select-playlist-to").value;
+  }
+
+  const template = document.querySelector("#input-playlist-template").value.trim();
+  return template ? "template:" + template : "";
+}
+
 function onInputUpdate() {
   const from_input = document.querySelector("#input-playlist-from");
   const from_select = document.querySelector("#select-playlist-from");
 
   const from = manualEntry ? from_input.value : from_select.value;
-  const to = document.querySelector("#select-playlist-to").value;
-  const sync_interval = document.querySelector("#input-sync-interval").value;
+  const to = getPlaylistTo();
+  const schedule = document.querySelector("#input-schedule").value.trim();
+
+  const invalid = !from || !to || !schedule || from === to;
+  document.querySelector("#submit").disabled = invalid;
+  document.querySelector("#preview").disabled = invalid;
+  document.querySelector("#preview-results").classList.add("hidden");
+}
+
+/** Read the create form into the body expected by the watcher endpoints */
+function getWatcherFormData() {
+  const from_select = document.querySelector("#select-playlist-from").value;
+  const from_input = document.querySelector("#input-playlist-from").value;
+  const playlist_to = getPlaylistTo();
+  const should_remove = document.querySelector(
+    "#checkbox-should-remove",
+  ).checked;
+  const schedule = document.querySelector("#input-schedule").value;
+
+  return {
+    playlist_from: manualEntry ? from_input : from_select,
+    playlist_to,
+    should_remove: manualEntry ? false : should_remove,
+    schedule,
+    time_zone: timeZone,
+    rules: getRulesFormData(document.querySelector("#create-rules")),
+    options: getOptionsFormData(document.querySelector("#create-options")),
+    extra_sources: getSelectedValues(document.querySelector("#select-extra-sources")),
+    extra_targets: getSelectedValues(document.querySelector("#select-extra-targets")),
+  };
+}
+
+/** @param {HTMLSelectElement} select */
+function getSelectedValues(select) {
+  return Array.from(select.selectedOptions).map((option) => option.value);
+}
+
+/**
+ * Read the filter rule inputs within an element, leaving out any that are blank
+ * @param {HTMLElement} container
+ */
+function getRulesFormData(container) {
+  const rules = {};
+
+  for (const input of container.querySelectorAll("[data-rule]")) {
+    const key = input.dataset.rule;
+
+    if (input.type === "checkbox") {
+      rules[key] = input.checked;
+    } else if (input.hasAttribute("data-list")) {
+      rules[key] = input.value
+        .split(",")
+        .map((value) => value.trim())
+        .filter(Boolean);
+    } else if (input.value !== "") {
+      rules[key] = Number(input.value);
+    }
+  }
+
+  return rules;
+}
+
+/** @param {string} id */
+async function saveRules(id) {
+  clearErrors();
+
+  const res = await fetch(`/watchers/${id}/rules`, {
+    method: "PUT",
+    headers,
+    body: JSON.stringify(getRulesFormData(document.querySelector(`#rules-${id}`))),
+  });
+  const data = await res.json();
+  if (!data.success) return setError(formatError(data.error));
+
+  refresh();
+}
+
+/**
+ * Read the transfer option inputs within an element
+ * @param {HTMLElement} container
+ */
+function getOptionsFormData(container) {
+  const options = {};
+
+  for (const input of container.querySelectorAll("[data-option]")) {
+    const key = input.dataset.option;
 
-  document.querySelector("#submit").disabled =
-    !from || !to || !sync_interval || from === to;
+    if (input.type === "checkbox") {
+      options[key] = input.checked;
+    } else if (input.type === "number") {
+      if (input.value !== "") options[key] = Number(input.value);
+    } else if (input.value !== "") {
+      options[key] = input.value;
+    }
+  }
+
+  return options;
+}
+
+/** @param {string} id */
+async function saveOptions(id) {
+  clearErrors();
+
+  const res = await fetch(`/watchers/${id}/options`, {
+    method: "PUT",
+    headers,
+    body: JSON.stringify(getOptionsFormData(document.querySelector(`#options-${id}`))),
+  });
+  const data = await res.json();
+  if (!data.success) return setError(formatError(data.error));
+
+  refresh();
+}
+
+/** @param {string} id */
+async function updateWatcher(id) {
+  clearErrors();
+
+  const container = document.querySelector(`#edit-${id}`);
+  const playlistTo = container.querySelector('[data-field="playlist_to"]').value;
+  const res = await fetch(`/watchers/${id}`, {
+    method: "PATCH",
+    headers,
+    body: JSON.stringify({
+      playlist_to: playlistTo || undefined,
+      schedule: container.querySelector('[data-field="schedule"]').value,
+      time_zone: timeZone,
+      should_remove: container.querySelector('[data-field="should_remove"]').checked,
+    }),
+  });
+  const data = await res.json();
+  if (!data.success) return setError(formatError(data.error));
+
+  refresh();
+}
+
+/**
+ * @param {string} id
+ * @param {boolean} enabled
+ */
+async function setWatcherEnabled(id, enabled) {
+  clearErrors();
+
+  const res = await fetch(`/watchers/${id}`, {
+    method: "PATCH",
+    headers,
+    body: JSON.stringify({ enabled }),
+  });
+  const data = await res.json();
+  if (!data.success) return setError(formatError(data.error));
+
+  refresh();
+}
+
+/**
+ * Validation errors are returned per field, so flatten them into a readable message
+ * @param {any} error
+ */
+function formatError(error) {
+  if (typeof error === "string" || !error?.fields) return error;
+
+  return Object.entries(error.fields)
+    .map(([field, errors]) => `${field}: ${errors.map((err) => err.message ?? err.code).join(", ")}`)
+    .join("; ");
+}
+
+/**
+ * @param {string} title
+ * @param {Array<{ name: string, artists: string[] }>} tracks
+ */
+function renderPreviewList(title, tracks) {
+  const section = document.createElement("div");
+  const heading = document.createElement("h4");
+  heading.textContent = `${title} (${tracks.length})`;
+  section.appendChild(heading);
+
+  const list = document.createElement("ul");
+  for (const track of tracks) {
+    const item = document.createElement("li");
+    item.textContent = `${track.artists.join(", ")} - ${track.name}`;
+    list.appendChild(item);
+  }
+  section.appendChild(list);
+
+  return section;
+}
+
+async function previewWatcher() {
+  clearErrors();
+
+  const res = await fetch("/watchers/preview", {
+    method: "POST",
+    headers,
+    body: JSON.stringify(getWatcherFormData()),
+  });
+
+  const data = await res.json();
+  if (!data.success) return setError(formatError(data.error));
+
+  const results = document.querySelector("#preview-results");
+  results.replaceChildren(
+    renderPreviewList("Will be added", data.preview.added),
+    renderPreviewList("Already in target", data.preview.skipped_duplicates),
+    renderPreviewList("Will be removed", data.preview.removed),
+    renderPreviewList("Local files, can't be transferred", data.preview.skipped_local),
+    renderPreviewList("Episodes, can't be saved to Liked Tracks", data.preview.skipped_unsupported),
+    renderPreviewList("Will be evicted to make room", data.preview.evicted),
+  );
+  results.classList.remove("hidden");
 }
 
 async function deleteUser() {
@@ -100,27 +338,14 @@ document.querySelector("form#create")?.addEventListener(
     clearErrors();
     e.preventDefault();
 
-    const from_select = document.querySelector("#select-playlist-from").value;
-    const from_input = document.querySelector("#input-playlist-from").value;
-    const playlist_to = document.querySelector("#select-playlist-to").value;
-    const should_remove = document.querySelector(
-      "#checkbox-should-remove",
-    ).checked;
-    const sync_interval = document.querySelector("#input-sync-interval").value;
-
     const res = await fetch("/watchers", {
       method: "POST",
       headers,
-      body: JSON.stringify({
-        playlist_from: manualEntry ? from_input : from_select,
-        playlist_to,
-        should_remove: manualEntry ? false : should_remove,
-        sync_interval,
-      }),
+      body: JSON.stringify(getWatcherFormData()),
     });
 
     const data = await res.json();
-    if (!data.success) return setError(data.error);
+    if (!data.success) return setError(formatError(data.error));
 
     refresh();
   },